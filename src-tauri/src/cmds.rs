@@ -9,8 +9,22 @@ use std::time::Duration;
 
 type CmdResult<T = ()> = Result<T, String>;
 
+fn device_of(device_id: &Option<String>) -> CmdResult<Device> {
+    match get_device(device_id) {
+        Ok(device) => Ok(device),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+fn motor_of(device_id: &Option<String>) -> CmdResult<MotorRef> {
+    match get_motor(device_id) {
+        Ok(motor) => Ok(motor),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 #[tauri::command]
-pub async fn init_serial_port(sp: &str, baud: u32) -> CmdResult {
+pub async fn init_serial_port(sp: &str, baud: u32, device_id: Option<String>) -> CmdResult {
     // 初始化 SerialPort 实例
     let result = serialport::new(sp, baud)
         .timeout(Duration::from_millis(50))
@@ -18,8 +32,7 @@ pub async fn init_serial_port(sp: &str, baud: u32) -> CmdResult {
 
     match result {
         Ok(port_new) => {
-            MOTOR.lock().unwrap().port = Some(port_new);
-            // MOTOR.lock().unwrap().reset_motor().unwrap();
+            open_device(&device_id, port_new);
             return Ok(());
         }
         Err(_) => {
@@ -29,11 +42,16 @@ pub async fn init_serial_port(sp: &str, baud: u32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn deinit_serial_port() -> CmdResult {
-    MOTOR.lock().unwrap().port = None;
+pub async fn deinit_serial_port(device_id: Option<String>) -> CmdResult {
+    close_device(&device_id);
     Ok(())
 }
 
+#[tauri::command]
+pub async fn list_connected_devices() -> CmdResult<Vec<DeviceInfo>> {
+    Ok(list_devices())
+}
+
 #[tauri::command]
 pub async fn list_avaliable_ports() -> CmdResult<Vec<String>> {
     let mut ports = Vec::new();
@@ -46,50 +64,67 @@ pub async fn list_avaliable_ports() -> CmdResult<Vec<String>> {
 }
 
 #[tauri::command]
-pub async fn get_motor_current_rps() -> CmdResult<f32> {
-    let rps = MOTOR.lock().unwrap().get_current_rps().unwrap();
+pub async fn get_motor_current_rps(device_id: Option<String>) -> CmdResult<f32> {
+    let motor = motor_of(&device_id)?;
+    let rps = motor.lock().unwrap().get_current_rps().unwrap();
 
     Ok(rps)
 }
 
 #[tauri::command]
-pub async fn get_motor_current_pos() -> CmdResult<f32> {
-    let pos = MOTOR.lock().unwrap().get_current_pos().unwrap();
+pub async fn get_motor_current_pos(device_id: Option<String>) -> CmdResult<f32> {
+    let motor = motor_of(&device_id)?;
+    let pos = motor.lock().unwrap().get_current_pos().unwrap();
 
     Ok(pos)
 }
 
 #[tauri::command]
-pub async fn get_motor_params() -> CmdResult<MotorParams> {
-    let params = MOTOR.lock().unwrap().get_motor_params().unwrap();
+pub async fn get_motor_params(device_id: Option<String>) -> CmdResult<MotorParams> {
+    let motor = motor_of(&device_id)?;
+    let params = motor.lock().unwrap().get_motor_params().unwrap();
 
     Ok(params)
 }
 
 #[tauri::command]
-pub async fn get_motor_static_params() -> CmdResult<MotorStaticParams> {
-    let params = MOTOR.lock().unwrap().get_motor_static_params().unwrap();
+pub async fn get_motor_static_params(device_id: Option<String>) -> CmdResult<MotorStaticParams> {
+    let motor = motor_of(&device_id)?;
+    let params = motor.lock().unwrap().get_motor_static_params().unwrap();
 
     Ok(params)
 }
 
 #[tauri::command]
-pub async fn get_motor_status() -> CmdResult<MotorStatus> {
-    let status = MOTOR.lock().unwrap().get_motor_status().unwrap();
+pub async fn get_motor_status(device_id: Option<String>) -> CmdResult<MotorStatus> {
+    let motor = motor_of(&device_id)?;
+    let status = motor.lock().unwrap().get_motor_status().unwrap();
 
     Ok(status)
 }
 
 #[tauri::command]
-pub async fn get_motor_special_params() -> CmdResult<MotorSpecialParams> {
-    let params = MOTOR.lock().unwrap().get_motor_special_params().unwrap();
+pub async fn get_motor_special_params(device_id: Option<String>) -> CmdResult<MotorSpecialParams> {
+    let motor = motor_of(&device_id)?;
+    let params = motor.lock().unwrap().get_motor_special_params().unwrap();
 
     Ok(params)
 }
 
 #[tauri::command]
-pub async fn update_motor_rps(rps: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_rps(
+    rps: f32,
+    ramp: Option<SCurveParam>,
+    device_id: Option<String>,
+) -> CmdResult {
+    let device = device_of(&device_id)?;
+    if let Some(ramp) = ramp {
+        // 从当前转速按S曲线过渡到目标转速
+        return stream_ramp(&device, rps, &ramp, false);
+    }
+
+    device
+        .motor
         .lock()
         .unwrap()
         .update_motor_speed_rps((rps * 100000.0) as u32)
@@ -99,15 +134,17 @@ pub async fn update_motor_rps(rps: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn enable_motor_identify(en: bool) -> CmdResult {
-    MOTOR.lock().unwrap().set_motor_identify_enable(en).unwrap();
+pub async fn enable_motor_identify(en: bool, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor.lock().unwrap().set_motor_identify_enable(en).unwrap();
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn enable_motor_rs_online(en: bool) -> CmdResult {
-    MOTOR
+pub async fn enable_motor_rs_online(en: bool, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .set_motor_rs_online_enable(en)
@@ -117,8 +154,9 @@ pub async fn enable_motor_rs_online(en: bool) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn enable_motor_rs_recalc(en: bool) -> CmdResult {
-    MOTOR
+pub async fn enable_motor_rs_recalc(en: bool, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .set_motor_rs_recalc_enable(en)
@@ -128,15 +166,17 @@ pub async fn enable_motor_rs_recalc(en: bool) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn clear_motor_faults() -> CmdResult {
-    MOTOR.lock().unwrap().clear_motor_faults().unwrap();
+pub async fn clear_motor_faults(device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor.lock().unwrap().clear_motor_faults().unwrap();
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn update_motor_acc_max(hz: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_acc_max(hz: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_acc_max((hz * 1000.0) as u32)
@@ -146,8 +186,9 @@ pub async fn update_motor_acc_max(hz: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_acc_start(hz: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_acc_start(hz: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_acc_start((hz * 1000.0) as u32)
@@ -157,8 +198,9 @@ pub async fn update_motor_acc_start(hz: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_kp_spd(kp: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_kp_spd(kp: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_kp_spd((kp * 100000000.0) as u32)
@@ -168,8 +210,9 @@ pub async fn update_motor_kp_spd(kp: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_ki_spd(ki: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_ki_spd(ki: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_ki_spd((ki * 100000000.0) as u32)
@@ -179,8 +222,9 @@ pub async fn update_motor_ki_spd(ki: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_kp_iq(kp: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_kp_iq(kp: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_kp_iq((kp * 100000000.0) as u32)
@@ -190,8 +234,9 @@ pub async fn update_motor_kp_iq(kp: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_ki_iq(ki: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_ki_iq(ki: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_ki_iq((ki * 100000000.0) as u32)
@@ -201,8 +246,18 @@ pub async fn update_motor_ki_iq(ki: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn start_motor(rps: f32) -> CmdResult {
-    MOTOR
+pub async fn start_motor(
+    rps: f32,
+    ramp: Option<SCurveParam>,
+    device_id: Option<String>,
+) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let motor = &device.motor;
+    if let Some(ramp) = ramp {
+        return stream_ramp(&device, rps, &ramp, true);
+    }
+
+    motor
         .lock()
         .unwrap()
         .update_motor_speed_rps((rps * 100000.0) as u32)
@@ -210,22 +265,61 @@ pub async fn start_motor(rps: f32) -> CmdResult {
 
     std::thread::sleep(std::time::Duration::from_millis(100));
 
-    MOTOR.lock().unwrap().start_motor().unwrap();
+    motor.lock().unwrap().start_motor().unwrap();
 
     Ok(())
 }
 
+/// 从实测转速按S曲线过渡到 to, start 为 true 时先以实测转速启动电机
+///
+/// 设备上已有的S曲线下发会先被中断, 两路下发不会同时写设定转速
+fn stream_ramp(device: &Device, to: f32, ramp: &SCurveParam, start: bool) -> CmdResult {
+    if let Err(err) = ramp.validate() {
+        return Err(err.to_string());
+    }
+
+    let (_guard, running) = device.begin_ramp();
+    let motor = &device.motor;
+
+    // 电机未运行或读取失败(返回负值)时从 0 开始
+    let from = motor.lock().unwrap().get_current_rps().unwrap().max(0.0);
+    if start {
+        motor
+            .lock()
+            .unwrap()
+            .update_motor_speed_rps((from * 100000.0) as u32)
+            .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        motor.lock().unwrap().start_motor().unwrap();
+    }
+
+    let result = stream_scurve(motor, from, to, ramp, &running);
+    device.end_ramp(&running);
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 #[tauri::command]
-pub async fn stop_motor() -> CmdResult {
-    MOTOR.lock().unwrap().update_motor_speed_hz(0).unwrap();
-    MOTOR.lock().unwrap().stop_motor().unwrap();
-    // MOTOR.lock().unwrap().reset_motor().unwrap();
+pub async fn stop_motor(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let motor = &device.motor;
+    // 中断正在下发的S曲线
+    device.cancel_ramp();
+    motor.lock().unwrap().update_motor_speed_hz(0).unwrap();
+    motor.lock().unwrap().stop_motor().unwrap();
+    // motor.lock().unwrap().reset_motor().unwrap();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn enable_motor_pos_ctrl(en: bool, mode: u8) -> CmdResult {
-    MOTOR
+pub async fn enable_motor_pos_ctrl(en: bool, mode: u8, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .set_motor_pos_ctrl_enable(en, mode)
@@ -235,8 +329,9 @@ pub async fn enable_motor_pos_ctrl(en: bool, mode: u8) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_position(pos: f32) -> CmdResult {
-    MOTOR
+pub async fn update_motor_position(pos: f32, device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_position((pos * 1000.0) as u32)
@@ -246,8 +341,12 @@ pub async fn update_motor_position(pos: f32) -> CmdResult {
 }
 
 #[tauri::command]
-pub async fn update_motor_special_params(param: MotorSpecialParams) -> CmdResult {
-    MOTOR
+pub async fn update_motor_special_params(
+    param: MotorSpecialParams,
+    device_id: Option<String>,
+) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
         .lock()
         .unwrap()
         .update_motor_special_params(param)
@@ -256,13 +355,19 @@ pub async fn update_motor_special_params(param: MotorSpecialParams) -> CmdResult
 }
 
 #[tauri::command]
-pub async fn export_motor_special_params(param: MotorSpecialParams, path: String) -> CmdResult {
+pub async fn export_motor_special_params(
+    param: MotorSpecialParams,
+    path: String,
+    device_id: Option<String>,
+) -> CmdResult {
     // 检查路径是否合法
     if path.is_empty() {
         return Err("path is invalid".to_string());
     }
 
-    MOTOR
+    let motor = motor_of(&device_id)?;
+
+    motor
         .lock()
         .unwrap()
         .export_motor_special_params(param, path)
@@ -272,13 +377,18 @@ pub async fn export_motor_special_params(param: MotorSpecialParams, path: String
 }
 
 #[tauri::command]
-pub async fn import_motor_special_params(path: String) -> CmdResult<Value> {
+pub async fn import_motor_special_params(
+    path: String,
+    device_id: Option<String>,
+) -> CmdResult<Value> {
     // 检查路径是否合法
     if path.is_empty() {
         return Err("path is invalid".to_string());
     }
 
-    let params = MOTOR
+    let motor = motor_of(&device_id)?;
+
+    let params = motor
         .lock()
         .unwrap()
         .import_motor_special_params(path)
@@ -295,57 +405,82 @@ pub async fn upload_file(file: std::path::PathBuf) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn start_record_rps(path: String) -> CmdResult {
+pub async fn start_record_rps(path: String, device_id: Option<String>) -> CmdResult {
     // 检查路径是否合法
     if path.is_empty() {
         return Err("path is invalid".to_string());
     }
 
-    MOTOR.lock().unwrap().start_rps_record(&path).unwrap();
+    let motor = motor_of(&device_id)?;
+
+    motor.lock().unwrap().start_rps_record(&path).unwrap();
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_record_rps() -> CmdResult {
-    MOTOR.lock().unwrap().stop_rps_record().unwrap();
+pub async fn stop_record_rps(device_id: Option<String>) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor.lock().unwrap().stop_rps_record().unwrap();
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn start_startup_task(test_param: StartUpTestParam) -> CmdResult {
-    STARTUPTEST.lock().unwrap().start(test_param);
-
-    Ok(())
+pub async fn start_startup_task(
+    test_param: StartUpTestParam,
+    device_id: Option<String>,
+) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .startup_test
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), test_param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
 }
 
 #[tauri::command]
-pub async fn stop_startup_task() -> CmdResult {
-    STARTUPTEST.lock().unwrap().stop();
+pub async fn stop_startup_task(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.startup_test.lock().unwrap().stop();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_startup_test_result() -> CmdResult<TestResult> {
-    let result = STARTUPTEST.lock().unwrap().get_test_result().unwrap();
+pub async fn get_startup_test_result(device_id: Option<String>) -> CmdResult<TestResult> {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .startup_test
+        .lock()
+        .unwrap()
+        .get_test_result()
+        .unwrap();
 
     Ok(result)
 }
 
 #[tauri::command]
-pub async fn init_relay_port(sp: &str, baud: u32) -> CmdResult {
-    // 初始化 SerialPort 实例
-    let result = serialport::new(sp, baud)
-        .timeout(Duration::from_millis(50))
-        .open();
+pub async fn init_relay_port(
+    sp: &str,
+    baud: u32,
+    backend: Option<tools::PowerSwitchConfig>,
+) -> CmdResult {
+    let result = tools::open_power_switch(sp, baud, &backend.unwrap_or_default());
 
     match result {
-        Ok(port_new) => {
-            RELAY.lock().unwrap().port = Some(port_new);
-            RELAY.lock().unwrap().turn_on().unwrap();
-            std::thread::sleep(Duration::from_millis(500));
-            RELAY.lock().unwrap().turn_off().unwrap();
+        Ok(mut switch) => {
+            // 确认继电器应答后再替换当前的供电开关
+            if let Err(err) = switch.self_test() {
+                return Err(format!("relay not responding: {}", err));
+            }
+
+            *RELAY.lock().unwrap() = switch;
             return Ok(());
         }
         Err(e) => {
@@ -357,23 +492,105 @@ pub async fn init_relay_port(sp: &str, baud: u32) -> CmdResult {
 
 #[tauri::command]
 pub async fn deinit_relay_port() -> CmdResult {
-    RELAY.lock().unwrap().turn_off().unwrap();
-    RELAY.lock().unwrap().port = None;
+    let mut relay = RELAY.lock().unwrap();
+    // 手动供电时不提示操作员断电
+    if !relay.is_manual() {
+        relay.turn_off().ok();
+    }
+    *relay = Box::new(tools::Relay::new());
     println!("deinit relay port");
     Ok(())
 }
 
 #[tauri::command]
 pub async fn set_relay_power(on: bool) -> CmdResult {
-    if on {
-        RELAY.lock().unwrap().turn_on().unwrap();
+    let result = if on {
+        tools::switch_power(|relay| relay.turn_on())
     } else {
-        RELAY.lock().unwrap().turn_off().unwrap();
+        tools::switch_power(|relay| relay.turn_off())
+    };
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
     }
+}
+
+#[tauri::command]
+pub async fn set_relay_channel(channel: u8, on: bool) -> CmdResult {
+    let result = tools::switch_power(|relay| relay.set_channel(channel, on));
 
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_relay_channels() -> CmdResult<Vec<bool>> {
+    let result = RELAY.lock().unwrap().get_channels();
+
+    match result {
+        Ok(channels) => Ok(channels),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn confirm_power_switch(id: u32, confirmed: bool) -> CmdResult {
+    tools::confirm_power_switch(id, confirmed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn init_power_supply(link: tools::PowerSupplyLink) -> CmdResult<String> {
+    let result = tools::POWER_SUPPLY.lock().unwrap().connect(&link);
+
+    match result {
+        Ok(idn) => Ok(idn),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn deinit_power_supply() -> CmdResult {
+    tools::POWER_SUPPLY.lock().unwrap().disconnect();
     Ok(())
 }
 
+#[tauri::command]
+pub async fn set_power_supply(voltage: f64, current_limit: Option<f64>) -> CmdResult {
+    let mut supply = tools::POWER_SUPPLY.lock().unwrap();
+    let result = current_limit
+        .map_or(Ok(()), |current| supply.set_current_limit(current))
+        .and_then(|_| supply.set_voltage(voltage));
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn set_power_supply_output(on: bool) -> CmdResult {
+    let result = tools::POWER_SUPPLY.lock().unwrap().set_output(on);
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_power_supply_status() -> CmdResult<tools::PowerSupplyStatus> {
+    let result = tools::POWER_SUPPLY.lock().unwrap().get_status();
+
+    match result {
+        Ok(status) => Ok(status),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 #[tauri::command]
 pub async fn upgrade_motor_fw(path: String, sp: String, baud: u32) -> CmdResult {
     // 检查路径是否合法
@@ -391,6 +608,20 @@ pub async fn upgrade_motor_fw(path: String, sp: String, baud: u32) -> CmdResult
     }
 }
 
+#[tauri::command]
+pub async fn cancel_motor_fw_upgrade() -> CmdResult {
+    let handle = UPGRADE.lock().unwrap().cancel();
+    if let Some(handle) = handle {
+        handle.join().unwrap();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_upgrade_progress() -> CmdResult<UpgradeProgress> {
+    Ok(UPGRADE.lock().unwrap().get_progress())
+}
+
 /// 合并电机驱动固件, 合成固件的原理其实很简单, 就是将BootLoader的Hex文件的最后两行删除, 将App的Hex文件第一行删除, 然后将Application的Hex文件所有内容复制到BootLoader的Hex文件的最后面即可
 #[tauri::command]
 pub async fn merge_firmware(boot: String, app: String, output: String) -> CmdResult {
@@ -398,3 +629,570 @@ pub async fn merge_firmware(boot: String, app: String, output: String) -> CmdRes
 
     Ok(())
 }
+
+#[tauri::command]
+pub async fn run_speed_step_test(
+    param: StepTestParam,
+    device_id: Option<String>,
+) -> CmdResult<StepTestResult> {
+    let motor = motor_of(&device_id)?;
+    match run_step_test(&motor, &param) {
+        Ok(result) => Ok(result),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_spd_autotune(param: SpdAutoTuneParam, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .spd_autotune
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_spd_autotune(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.spd_autotune.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_spd_autotune_result(device_id: Option<String>) -> CmdResult<SpdAutoTuneResult> {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .spd_autotune
+        .lock()
+        .unwrap()
+        .get_result()
+        .unwrap();
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn apply_spd_autotune(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device.tasks.spd_autotune.lock().unwrap().apply();
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn calc_current_loop_gains(
+    param: CurrentLoopCalcParam,
+    device_id: Option<String>,
+) -> CmdResult<CurrentLoopGains> {
+    let motor = motor_of(&device_id)?;
+    let result = motor.lock().unwrap().calc_current_loop_gains(&param);
+
+    match result {
+        Ok(gains) => Ok(gains),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn apply_current_loop_gains(
+    gains: CurrentLoopGains,
+    device_id: Option<String>,
+) -> CmdResult {
+    let motor = motor_of(&device_id)?;
+    motor
+        .lock()
+        .unwrap()
+        .apply_current_loop_gains(&gains)
+        .unwrap();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_speed_profile_file(path: String) -> CmdResult<Vec<ProfilePoint>> {
+    match load_speed_profile(&path) {
+        Ok(points) => Ok(points),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_speed_profile(param: ProfileRunParam, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .profile_runner
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn pause_speed_profile(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.profile_runner.lock().unwrap().pause();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_speed_profile(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.profile_runner.lock().unwrap().resume();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn abort_speed_profile(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.profile_runner.lock().unwrap().abort();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_speed_profile_status(device_id: Option<String>) -> CmdResult<ProfileStatus> {
+    let device = device_of(&device_id)?;
+    let status = device
+        .tasks
+        .profile_runner
+        .lock()
+        .unwrap()
+        .get_status()
+        .unwrap();
+
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn get_startup_test_records(device_id: Option<String>) -> CmdResult<Vec<TrialRecord>> {
+    let device = device_of(&device_id)?;
+    let records = device
+        .tasks
+        .startup_test
+        .lock()
+        .unwrap()
+        .get_test_records()
+        .unwrap();
+
+    Ok(records)
+}
+
+#[tauri::command]
+pub async fn export_startup_test_report(path: String, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    // 检查路径是否合法
+    if path.is_empty() {
+        return Err("path is invalid".to_string());
+    }
+
+    let result = device
+        .tasks
+        .startup_test
+        .lock()
+        .unwrap()
+        .export_report(&path);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_startup_sweep(param: StartupSweepParam, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .startup_sweep
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_startup_sweep(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.startup_sweep.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_startup_sweep_result(device_id: Option<String>) -> CmdResult<StartupSweepResult> {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .startup_sweep
+        .lock()
+        .unwrap()
+        .get_result()
+        .unwrap();
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn apply_startup_sweep_best(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device.tasks.startup_sweep.lock().unwrap().apply_best();
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_voltage_protection_test(
+    param: VoltageProtectionParam,
+    device_id: Option<String>,
+) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .voltage_protection
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_voltage_protection_test(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.voltage_protection.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_voltage_protection_result(
+    device_id: Option<String>,
+) -> CmdResult<VoltageProtectionResult> {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .voltage_protection
+        .lock()
+        .unwrap()
+        .get_result()
+        .unwrap();
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn start_boot_time_test(param: BootTimeParam, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .boot_time
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_boot_time_test(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.boot_time.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_boot_time_result(device_id: Option<String>) -> CmdResult<BootTimeResult> {
+    let device = device_of(&device_id)?;
+    let result = device.tasks.boot_time.lock().unwrap().get_result().unwrap();
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn pause_startup_task(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.startup_test.lock().unwrap().pause();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn resume_startup_task(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.startup_test.lock().unwrap().resume();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_startup_test_checkpoint(path: String) -> CmdResult<StartupTestCheckpoint> {
+    match load_startup_checkpoint(&path) {
+        Ok(checkpoint) => Ok(checkpoint),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn resume_startup_task_from_checkpoint(
+    path: String,
+    device_id: Option<String>,
+) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .startup_test
+        .lock()
+        .unwrap()
+        .resume_from_checkpoint(device.motor.clone(), &path);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_endurance_test(
+    param: EnduranceTestParam,
+    device_id: Option<String>,
+) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .endurance_test
+        .lock()
+        .unwrap()
+        .start(device.motor.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_endurance_test(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.endurance_test.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_endurance_test_result(device_id: Option<String>) -> CmdResult<EnduranceResult> {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .endurance_test
+        .lock()
+        .unwrap()
+        .get_result()
+        .unwrap();
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn export_endurance_test_report(path: String, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    // 检查路径是否合法
+    if path.is_empty() {
+        return Err("path is invalid".to_string());
+    }
+
+    let result = device
+        .tasks
+        .endurance_test
+        .lock()
+        .unwrap()
+        .export_report(&path);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn load_test_plan_file(path: String) -> CmdResult<TestPlan> {
+    match load_test_plan(&path) {
+        Ok(plan) => Ok(plan),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_test_plan(path: String, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let plan = match load_test_plan(&path) {
+        Ok(plan) => plan,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let result = device
+        .tasks
+        .test_plan
+        .lock()
+        .unwrap()
+        .start(device.clone(), plan);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_test_plan(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.test_plan.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_test_plan_result(device_id: Option<String>) -> CmdResult<PlanResult> {
+    let device = device_of(&device_id)?;
+    let result = device.tasks.test_plan.lock().unwrap().get_result().unwrap();
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn export_test_plan_report(path: String, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    // 检查路径是否合法
+    if path.is_empty() {
+        return Err("path is invalid".to_string());
+    }
+
+    let result = device.tasks.test_plan.lock().unwrap().export_report(&path);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_station_test(param: StationRunParam, device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    let result = device
+        .tasks
+        .station
+        .lock()
+        .unwrap()
+        .start(device.clone(), param);
+    match result {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_station_test(device_id: Option<String>) -> CmdResult {
+    let device = device_of(&device_id)?;
+    device.tasks.station.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_station_status(device_id: Option<String>) -> CmdResult<StationStatus> {
+    let device = device_of(&device_id)?;
+    let status = device.tasks.station.lock().unwrap().get_status().unwrap();
+
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn get_station_history(
+    history_dir: String,
+    serial_number: String,
+) -> CmdResult<Vec<StationRecord>> {
+    match load_station_history(&history_dir, &serial_number) {
+        Ok(records) => Ok(records),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn start_startup_stations(params: Vec<StartupStationParam>) -> CmdResult {
+    match STARTUPSTATIONS.lock().unwrap().start(params) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn stop_startup_stations() -> CmdResult {
+    STARTUPSTATIONS.lock().unwrap().stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_startup_station(device_id: String) -> CmdResult {
+    match STARTUPSTATIONS.lock().unwrap().stop_station(&device_id) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn pause_startup_station(device_id: String) -> CmdResult {
+    match STARTUPSTATIONS.lock().unwrap().pause_station(&device_id) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn resume_startup_station(device_id: String) -> CmdResult {
+    match STARTUPSTATIONS.lock().unwrap().resume_station(&device_id) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn get_startup_stations_summary() -> CmdResult<StartupStationsSummary> {
+    let summary = STARTUPSTATIONS.lock().unwrap().get_summary().unwrap();
+
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn get_startup_station_records(device_id: String) -> CmdResult<Vec<TrialRecord>> {
+    let records = STARTUPSTATIONS
+        .lock()
+        .unwrap()
+        .get_station(&device_id)
+        .and_then(|station| station.get_test_records());
+
+    match records {
+        Ok(records) => Ok(records),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn export_startup_station_report(device_id: String, path: String) -> CmdResult {
+    // 检查路径是否合法
+    if path.is_empty() {
+        return Err("path is invalid".to_string());
+    }
+
+    let station = STARTUPSTATIONS.lock().unwrap().get_station(&device_id);
+    match station.and_then(|station| station.export_report(&path)) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
@@ -18,6 +18,13 @@ pub struct Logger {
 pub static LOGGER: Lazy<Mutex<Logger>> = Lazy::new(|| Mutex::new(Logger { app_handle: None }));
 
 impl Logger {
+    /// 向前端发送自定义事件, 用于上报进度等状态
+    pub fn emit<S: Serialize + Clone>(&mut self, event: &str, payload: S) {
+        if let Some(ref mut app) = self.app_handle {
+            app.emit_all(event, payload).unwrap();
+        }
+    }
+
     fn log_message(&mut self, log: &LogEntry) {
         if let Some(ref mut app) = self.app_handle {
             app.emit_all("log_event", log).unwrap();
@@ -22,6 +22,7 @@ async fn main() {
         .invoke_handler(tauri::generate_handler![
             cmds::init_serial_port,
             cmds::deinit_serial_port,
+            cmds::list_connected_devices,
             cmds::get_motor_current_rps,
             cmds::get_motor_current_pos,
             cmds::get_motor_params,
@@ -56,8 +57,68 @@ async fn main() {
             cmds::init_relay_port,
             cmds::deinit_relay_port,
             cmds::set_relay_power,
+            cmds::set_relay_channel,
+            cmds::get_relay_channels,
+            cmds::confirm_power_switch,
+            cmds::init_power_supply,
+            cmds::deinit_power_supply,
+            cmds::set_power_supply,
+            cmds::set_power_supply_output,
+            cmds::get_power_supply_status,
             cmds::upgrade_motor_fw,
+            cmds::get_upgrade_progress,
+            cmds::cancel_motor_fw_upgrade,
             cmds::merge_firmware,
+            cmds::run_speed_step_test,
+            cmds::start_spd_autotune,
+            cmds::stop_spd_autotune,
+            cmds::get_spd_autotune_result,
+            cmds::apply_spd_autotune,
+            cmds::calc_current_loop_gains,
+            cmds::apply_current_loop_gains,
+            cmds::load_speed_profile_file,
+            cmds::start_speed_profile,
+            cmds::pause_speed_profile,
+            cmds::resume_speed_profile,
+            cmds::abort_speed_profile,
+            cmds::get_speed_profile_status,
+            cmds::get_startup_test_records,
+            cmds::export_startup_test_report,
+            cmds::start_startup_sweep,
+            cmds::stop_startup_sweep,
+            cmds::get_startup_sweep_result,
+            cmds::apply_startup_sweep_best,
+            cmds::start_voltage_protection_test,
+            cmds::stop_voltage_protection_test,
+            cmds::get_voltage_protection_result,
+            cmds::start_boot_time_test,
+            cmds::stop_boot_time_test,
+            cmds::get_boot_time_result,
+            cmds::pause_startup_task,
+            cmds::resume_startup_task,
+            cmds::load_startup_test_checkpoint,
+            cmds::resume_startup_task_from_checkpoint,
+            cmds::start_endurance_test,
+            cmds::stop_endurance_test,
+            cmds::get_endurance_test_result,
+            cmds::export_endurance_test_report,
+            cmds::load_test_plan_file,
+            cmds::start_test_plan,
+            cmds::stop_test_plan,
+            cmds::get_test_plan_result,
+            cmds::export_test_plan_report,
+            cmds::start_station_test,
+            cmds::stop_station_test,
+            cmds::get_station_status,
+            cmds::get_station_history,
+            cmds::start_startup_stations,
+            cmds::stop_startup_stations,
+            cmds::stop_startup_station,
+            cmds::pause_startup_station,
+            cmds::resume_startup_station,
+            cmds::get_startup_stations_summary,
+            cmds::get_startup_station_records,
+            cmds::export_startup_station_report,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,10 +1,11 @@
+use crate::motor::{
+    calc_current_loop_gains, CurrentLoopCalcParam, CurrentLoopGains, TelemetrySample,
+};
 use crate::tools;
 use anyhow::{bail, Ok, Result};
-use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serialport::{self, SerialPort};
-use std::sync::Mutex;
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct MotorParams {
@@ -48,7 +49,7 @@ struct RpsRecoder {
 }
 
 #[allow(dead_code)]
-enum GetCmdTypes {
+pub(crate) enum GetCmdTypes {
     GetMotorRps,
     GetMotorPos,
     GetVersion,
@@ -235,7 +236,9 @@ pub struct Motor {
     pub recoder_handle: Option<Box<csv::Writer<std::fs::File>>>,
 }
 
-pub static MOTOR: Lazy<Mutex<Motor>> = Lazy::new(|| Mutex::new(Motor::new()));
+/// GetIa0..2 应答的换算系数. 协议未单独注明相电流的单位, 这里按与 GetAlignCurrent 等电流参数相同的 mA 处理,
+/// 固件使用其他单位时需修改此处
+const PHASE_CURRENT_SCALE: f32 = 1000.0;
 
 #[allow(dead_code)]
 impl Motor {
@@ -409,6 +412,16 @@ impl Motor {
         })
     }
 
+    /// 只读取固件版本, 用于判断控制器是否已启动完成并开始应答
+    pub fn probe_version(&mut self) -> Option<String> {
+        let buf = self.request(GetCmdTypes::GetVersion as u8, 0)?;
+        if buf.len() < 4 {
+            return None;
+        }
+
+        Some(format!("{}.{}.{}{}", buf[0], buf[1], buf[2], buf[3] as char))
+    }
+
     #[allow(unused_assignments)]
     pub fn get_motor_params(&mut self) -> Result<MotorParams> {
         let mut vdc_bus = 0.0;
@@ -467,6 +480,56 @@ impl Motor {
         })
     }
 
+    pub fn get_rs_online(&mut self) -> Result<f32> {
+        let mut rs = 0.0;
+
+        if let Some(buf) = self.request(GetCmdTypes::GetParamRsOnline as u8, 0) {
+            if buf.len() >= 4 {
+                rs = vec_to_int(&buf[0..4]) as f32 / 100000000.0;
+            }
+        }
+
+        Ok(rs)
+    }
+
+    pub fn get_phase_currents(&mut self) -> Result<(f32, f32, f32)> {
+        let mut currents = [0.0; 3];
+        let cmds = [
+            GetCmdTypes::GetIa0 as u8,
+            GetCmdTypes::GetIa1 as u8,
+            GetCmdTypes::GetIa2 as u8,
+        ];
+
+        for (i, cmd) in cmds.iter().enumerate() {
+            if let Some(buf) = self.request(*cmd, 0) {
+                if buf.len() >= 4 {
+                    currents[i] = vec_to_int(&buf[0..4]) as f32 / PHASE_CURRENT_SCALE;
+                }
+            }
+        }
+
+        Ok((currents[0], currents[1], currents[2]))
+    }
+
+    /// 采集一帧遥测数据: 转速、状态、故障码、母线电压和三相电流
+    pub fn get_motor_telemetry(&mut self, time_ms: u64) -> Result<TelemetrySample> {
+        let rps = self.get_current_rps()?;
+        let status = self.get_motor_status()?;
+        let params = self.get_motor_params()?;
+        let (ia, ib, ic) = self.get_phase_currents()?;
+
+        Ok(TelemetrySample {
+            time_ms,
+            rps,
+            error_code: status.error_code.unwrap_or_default(),
+            motor_state: status.motor_state.unwrap_or_default(),
+            vdc_bus: params.vdc_bus.unwrap_or_default(),
+            ia,
+            ib,
+            ic,
+        })
+    }
+
     pub fn get_motor_special_params(&mut self) -> Result<MotorSpecialParams> {
         let motor_feature_param = self.get_motor_feature_params().unwrap();
 
@@ -865,6 +928,27 @@ impl Motor {
         Ok(())
     }
 
+    pub fn calc_current_loop_gains(
+        &mut self,
+        param: &CurrentLoopCalcParam,
+    ) -> Result<CurrentLoopGains> {
+        let static_params = self.get_motor_static_params()?;
+        let rs = static_params.rs.unwrap_or_default();
+        let ls = if param.use_ls_d {
+            static_params.ls_d.unwrap_or_default()
+        } else {
+            static_params.ls_q.unwrap_or_default()
+        };
+
+        calc_current_loop_gains(rs, ls, param)
+    }
+
+    pub fn apply_current_loop_gains(&mut self, gains: &CurrentLoopGains) -> Result<()> {
+        self.update_motor_kp_iq((gains.kp_iq * 100000000.0) as u32)?;
+        self.update_motor_ki_iq((gains.ki_iq * 100000000.0) as u32)?;
+        Ok(())
+    }
+
     pub fn set_motor_pos_ctrl_enable(&mut self, en: bool, mode: u8) -> Result<()> {
         if let Some(_) = self.request(
             SetCmdTypes::SetEnablePosCtrl as u8,
@@ -878,7 +962,7 @@ impl Motor {
         Ok(())
     }
 
-    fn request(&mut self, msg_type: u8, msg: i32) -> Option<Vec<u8>> {
+    pub(crate) fn request(&mut self, msg_type: u8, msg: i32) -> Option<Vec<u8>> {
         let mut cmd: Vec<u8> = vec![];
         cmd.push(0x5a);
         cmd.push(0x5a);
@@ -1210,3 +1294,32 @@ pub fn vec_to_int(buf: &[u8]) -> i32 {
 pub fn vec_to_short(buf: &[u8]) -> i16 {
     ((buf[0] as i16) << 8 | (buf[1] as i16)) as i16
 }
+
+/// 故障码各bit的含义, 与前端 parseErrorCode.js 保持一致
+const FAULT_BITS: [&str; 16] = [
+    "overVoltage",
+    "underVoltage",
+    "motorOverTemp",
+    "moduleOverTemp",
+    "moduleOverCurrent",
+    "overPeakCurrent",
+    "overLoad",
+    "motorLostPhase",
+    "currentUnbalance",
+    "motorStall",
+    "startupFailed",
+    "overSpeed",
+    "reserve12",
+    "reserve13",
+    "currentOffset",
+    "voltageOffset",
+];
+
+pub fn decode_fault_bits(error_code: u16) -> Vec<String> {
+    FAULT_BITS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| error_code & (1 << i) != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
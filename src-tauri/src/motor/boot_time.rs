@@ -0,0 +1,380 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use crate::tools::switch_power;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 电机接受启动命令后进入的状态, 通信失败时 get_motor_status 返回的占位状态不在其中
+const STARTED_STATES: [&str; 4] = ["SEEK_POS", "ALIGNMENT", "OL_START", "CL_RUNNING"];
+
+fn default_off_ms() -> u64 {
+    3000
+}
+
+fn default_boot_timeout_ms() -> u64 {
+    10000
+}
+
+fn default_poll_interval_ms() -> u64 {
+    20
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct BootTimeParam {
+    pub cycles: u32,
+    pub target_rps: f32,
+    #[serde(default = "default_off_ms")]
+    pub off_ms: u64, // 每次断电的时间, 需保证控制器完全掉电
+    #[serde(default = "default_boot_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    #[serde(default)]
+    pub relay_channel: Option<u8>, // 只控制该路继电器, 为空时控制全部通道
+    #[serde(default)]
+    pub save_path: Option<String>,
+}
+
+/// 一次上电的测量结果, 时间均从继电器上电开始计算
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct BootTimeSample {
+    pub index: u32,
+    pub answer_ms: Option<u64>, // 首次正确应答 GetVersion 的时间
+    pub start_ms: Option<u64>,  // 首次接受启动命令的时间
+    pub version: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct BootTimeStats {
+    pub count: usize,
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub mean_ms: f64,
+    pub std_ms: f64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct BootTimeResult {
+    pub running: bool,
+    pub cycle: u32,
+    pub total: u32,
+    pub samples: Vec<BootTimeSample>,
+    pub answer_stats: Option<BootTimeStats>,
+    pub start_stats: Option<BootTimeStats>,
+    pub failed_cnt: u32,
+    pub error: Option<String>, // 测量被中止的原因, 如继电器未确认
+}
+
+/// 等待控制器应答 GetVersion, 返回应答时间和版本号; 超时或测试被停止时返回 None
+pub fn wait_for_answer(
+    motor: &MotorRef,
+    since: &Instant,
+    timeout_ms: u64,
+    poll_interval_ms: u64,
+    running: &AtomicBool,
+) -> Option<(u64, String)> {
+    while running.load(Ordering::SeqCst) && since.elapsed().as_millis() < timeout_ms as u128 {
+        if let Some(version) = motor.lock().unwrap().probe_version() {
+            return Some((since.elapsed().as_millis() as u64, version));
+        }
+        thread::sleep(Duration::from_millis(poll_interval_ms));
+    }
+
+    None
+}
+
+fn calc_stats(values: &[u64]) -> Option<BootTimeStats> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort();
+
+    let n = sorted.len() as f64;
+    let mean = sorted.iter().sum::<u64>() as f64 / n;
+    let var = sorted
+        .iter()
+        .map(|v| (*v as f64 - mean) * (*v as f64 - mean))
+        .sum::<f64>()
+        / n;
+    // 最近秩法取分位数
+    let percentile = |p: f64| sorted[((p * n).ceil() as usize).clamp(1, sorted.len()) - 1];
+
+    Some(BootTimeStats {
+        count: sorted.len(),
+        min_ms: sorted[0],
+        max_ms: sorted[sorted.len() - 1],
+        mean_ms: mean,
+        std_ms: libm::sqrt(var),
+        p50_ms: percentile(0.5),
+        p95_ms: percentile(0.95),
+    })
+}
+
+/// 上电启动时间测量: 反复断电/上电, 统计控制器开始应答和可以启动电机所需的时间
+pub struct BootTimeHandle {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    result: Arc<Mutex<BootTimeResult>>,
+}
+
+impl BootTimeHandle {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            result: Arc::new(Mutex::new(BootTimeResult::default())),
+        }
+    }
+
+    pub fn start(&self, motor: MotorRef, param: BootTimeParam) -> Result<()> {
+        if param.cycles == 0 {
+            bail!("cycles must be greater than 0");
+        }
+
+        if motor.lock().unwrap().port.is_none() {
+            bail!("motor port is not connected");
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("boot time test is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        *self.result.lock().unwrap() = BootTimeResult {
+            running: true,
+            total: param.cycles,
+            ..Default::default()
+        };
+
+        let running = Arc::clone(&self.running);
+        let result = Arc::clone(&self.result);
+        running.store(true, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            LOGGER
+                .lock()
+                .unwrap()
+                .info(format!("开始上电启动时间测量, 共{}次", param.cycles).as_str());
+
+            for cycle in 1..=param.cycles {
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+                result.lock().unwrap().cycle = cycle;
+
+                let sample = match Self::measure_cycle(&motor, &param, cycle, &running) {
+                    core::result::Result::Ok(Some(sample)) => sample,
+                    core::result::Result::Ok(None) => break,
+                    Err(err) => {
+                        LOGGER
+                            .lock()
+                            .unwrap()
+                            .danger(format!("上电启动时间测量中止: {}", err).as_str());
+                        result.lock().unwrap().error = Some(err.to_string());
+                        break;
+                    }
+                };
+
+                match sample.error {
+                    Some(ref err) => LOGGER
+                        .lock()
+                        .unwrap()
+                        .danger(format!("第{}次上电: {}", cycle, err).as_str()),
+                    None => LOGGER.lock().unwrap().info(
+                        format!(
+                            "第{}次上电: 应答 {}ms, 启动 {}ms",
+                            cycle,
+                            sample.answer_ms.unwrap_or_default(),
+                            sample.start_ms.unwrap_or_default()
+                        )
+                        .as_str(),
+                    ),
+                }
+
+                let mut r = result.lock().unwrap();
+                if sample.error.is_some() {
+                    r.failed_cnt += 1;
+                }
+                r.samples.push(sample);
+                let answers: Vec<u64> = r.samples.iter().filter_map(|s| s.answer_ms).collect();
+                let starts: Vec<u64> = r.samples.iter().filter_map(|s| s.start_ms).collect();
+                r.answer_stats = calc_stats(&answers);
+                r.start_stats = calc_stats(&starts);
+            }
+
+            if let Some(ref path) = param.save_path {
+                if !path.is_empty() {
+                    if let Err(err) = Self::save_samples(&result.lock().unwrap().samples, path) {
+                        LOGGER
+                            .lock()
+                            .unwrap()
+                            .warning(format!("保存测量结果失败: {}", err).as_str());
+                    }
+                }
+            }
+
+            LOGGER.lock().unwrap().warning("上电启动时间测量结束");
+            result.lock().unwrap().running = false;
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    /// 断电 - 上电 - 等待应答 - 等待可启动 - 停机, 测试被停止时返回 None
+    fn measure_cycle(
+        motor: &MotorRef,
+        param: &BootTimeParam,
+        cycle: u32,
+        running: &AtomicBool,
+    ) -> Result<Option<BootTimeSample>> {
+        Self::relay_power(param, false)?;
+        let off = Instant::now();
+        while running.load(Ordering::SeqCst) && off.elapsed().as_millis() < param.off_ms as u128 {
+            thread::sleep(Duration::from_millis(50));
+        }
+        if !running.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        Self::relay_power(param, true)?;
+        let since = Instant::now();
+
+        let mut sample = BootTimeSample {
+            index: cycle,
+            answer_ms: None,
+            start_ms: None,
+            version: None,
+            error: None,
+        };
+
+        match wait_for_answer(
+            motor,
+            &since,
+            param.timeout_ms,
+            param.poll_interval_ms,
+            running,
+        ) {
+            Some((ms, version)) => {
+                sample.answer_ms = Some(ms);
+                sample.version = Some(version);
+            }
+            None if !running.load(Ordering::SeqCst) => return Ok(None),
+            None => {
+                sample.error = Some(String::from("no answer"));
+                return Ok(Some(sample));
+            }
+        }
+
+        // 应答后控制器可能仍在初始化(如电流零偏校准), 反复发送启动命令直到电机无故障地进入运行过程,
+        // 请求失败视为尚未接受启动
+        motor
+            .lock()
+            .unwrap()
+            .update_motor_speed_rps((param.target_rps * 100000.0) as u32)?;
+        while running.load(Ordering::SeqCst)
+            && since.elapsed().as_millis() < param.timeout_ms as u128
+        {
+            let status = {
+                let mut motor = motor.lock().unwrap();
+                motor.start_motor().and_then(|_| motor.get_motor_status())
+            };
+
+            let started = status.is_ok_and(|status| {
+                status.error_code == Some(0)
+                    && status
+                        .motor_state
+                        .is_some_and(|s| STARTED_STATES.contains(&s.as_str()))
+            });
+            if started {
+                sample.start_ms = Some(since.elapsed().as_millis() as u64);
+                break;
+            }
+            thread::sleep(Duration::from_millis(param.poll_interval_ms));
+        }
+
+        motor.lock().unwrap().stop_motor().ok();
+
+        if sample.start_ms.is_none() {
+            if !running.load(Ordering::SeqCst) {
+                return Ok(None);
+            }
+            sample.error = Some(String::from("start not accepted"));
+        }
+
+        Ok(Some(sample))
+    }
+
+    fn relay_power(param: &BootTimeParam, on: bool) -> Result<()> {
+        switch_power(|relay| match (param.relay_channel, on) {
+            (Some(channel), on) => relay.set_channel(channel, on),
+            (None, true) => relay.turn_on(),
+            (None, false) => relay.turn_off(),
+        })
+    }
+
+    fn save_samples(samples: &[BootTimeSample], path: &str) -> Result<()> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        for sample in samples {
+            wtr.serialize(sample)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_result(&self) -> Result<BootTimeResult> {
+        Ok(self.result.lock().unwrap().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calc_stats_basic() {
+        let stats = calc_stats(&[400, 100, 300, 200]).unwrap();
+
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.min_ms, 100);
+        assert_eq!(stats.max_ms, 400);
+        assert_eq!(stats.mean_ms, 250.0);
+        assert!((stats.std_ms - libm::sqrt(12500.0)).abs() < 1e-9);
+        assert_eq!(stats.p50_ms, 200);
+        assert_eq!(stats.p95_ms, 400);
+    }
+
+    #[test]
+    fn calc_stats_nearest_rank() {
+        let values: Vec<u64> = (1..=20).collect();
+        let stats = calc_stats(&values).unwrap();
+
+        assert_eq!(stats.p50_ms, 10);
+        assert_eq!(stats.p95_ms, 19);
+        assert_eq!(calc_stats(&[7]).unwrap().p95_ms, 7);
+        assert_eq!(calc_stats(&[]), None);
+    }
+}
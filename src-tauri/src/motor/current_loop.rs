@@ -0,0 +1,171 @@
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PiForm {
+    // u = kp * e + ki * ∫e
+    #[default]
+    Parallel,
+    // u = kp * (e + ki * ∫e)
+    Series,
+}
+
+fn default_base() -> f64 {
+    1.0
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct CurrentLoopCalcParam {
+    pub bandwidth_hz: f64,
+    #[serde(default)]
+    pub form: PiForm,
+    #[serde(default)]
+    pub ctrl_freq_hz: Option<f64>, // 电流环控制频率, 给定时积分增益按每个控制周期换算
+    #[serde(default = "default_base")]
+    pub current_base: f64, // 标幺化基值, 默认1表示使用实际单位(V/A)
+    #[serde(default = "default_base")]
+    pub voltage_base: f64,
+    #[serde(default)]
+    pub use_ls_d: bool, // 默认使用q轴电感
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct CurrentLoopGains {
+    pub rs: f64,
+    pub ls: f64,
+    pub bandwidth_hz: f64,
+    pub kp_iq: f64,
+    pub ki_iq: f64,
+}
+
+/// 根据辨识得到的 Rs/Ls 和期望带宽计算电流环PI参数
+///
+/// 以PI零点抵消电机电气极点(R/L), 闭环近似为带宽 ωc 的一阶系统:
+/// kp = Ls * ωc, 并联形式 ki = Rs * ωc, 串联形式 ki = Rs / Ls
+pub fn calc_current_loop_gains(
+    rs: f64,
+    ls: f64,
+    param: &CurrentLoopCalcParam,
+) -> Result<CurrentLoopGains> {
+    if rs <= 0.0 || ls <= 0.0 {
+        bail!("motor parameters are not identified");
+    }
+
+    if param.bandwidth_hz <= 0.0 || param.current_base <= 0.0 || param.voltage_base <= 0.0 {
+        bail!("invalid bandwidth or base value");
+    }
+
+    let wc = 2.0 * std::f64::consts::PI * param.bandwidth_hz;
+    // 标幺化: 电流误差(pu) -> 电压输出(pu)
+    let scale = param.current_base / param.voltage_base;
+
+    let kp_iq = ls * wc * scale;
+    let mut ki_iq = match param.form {
+        PiForm::Parallel => rs * wc * scale,
+        PiForm::Series => rs / ls,
+    };
+
+    if let Some(freq) = param.ctrl_freq_hz {
+        if freq <= 0.0 {
+            bail!("invalid control frequency");
+        }
+        ki_iq /= freq;
+    }
+
+    Ok(CurrentLoopGains {
+        rs,
+        ls,
+        bandwidth_hz: param.bandwidth_hz,
+        kp_iq,
+        ki_iq,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn param() -> CurrentLoopCalcParam {
+        CurrentLoopCalcParam {
+            bandwidth_hz: 1000.0,
+            form: PiForm::Parallel,
+            ctrl_freq_hz: None,
+            current_base: 1.0,
+            voltage_base: 1.0,
+            use_ls_d: false,
+        }
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9 * b.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn parallel_gains() {
+        let gains = calc_current_loop_gains(0.5, 0.001, &param()).unwrap();
+        let wc = 2.0 * PI * 1000.0;
+
+        assert_close(gains.kp_iq, 0.001 * wc);
+        assert_close(gains.ki_iq, 0.5 * wc);
+        assert_eq!(gains.bandwidth_hz, 1000.0);
+    }
+
+    #[test]
+    fn series_gains() {
+        let param = CurrentLoopCalcParam {
+            form: PiForm::Series,
+            ..param()
+        };
+        let gains = calc_current_loop_gains(0.5, 0.001, &param).unwrap();
+
+        assert_close(gains.kp_iq, 0.001 * 2.0 * PI * 1000.0);
+        assert_close(gains.ki_iq, 500.0);
+    }
+
+    #[test]
+    fn per_unit_scale_and_ctrl_freq() {
+        let param = CurrentLoopCalcParam {
+            ctrl_freq_hz: Some(20000.0),
+            current_base: 10.0,
+            voltage_base: 24.0,
+            ..param()
+        };
+        let gains = calc_current_loop_gains(0.5, 0.001, &param).unwrap();
+        let wc = 2.0 * PI * 1000.0;
+        let scale = 10.0 / 24.0;
+
+        // 比例增益不随控制频率换算
+        assert_close(gains.kp_iq, 0.001 * wc * scale);
+        assert_close(gains.ki_iq, 0.5 * wc * scale / 20000.0);
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(calc_current_loop_gains(0.0, 0.001, &param()).is_err());
+        assert!(calc_current_loop_gains(0.5, -0.001, &param()).is_err());
+
+        let invalid = [
+            CurrentLoopCalcParam {
+                bandwidth_hz: 0.0,
+                ..param()
+            },
+            CurrentLoopCalcParam {
+                current_base: 0.0,
+                ..param()
+            },
+            CurrentLoopCalcParam {
+                voltage_base: -1.0,
+                ..param()
+            },
+            CurrentLoopCalcParam {
+                ctrl_freq_hz: Some(0.0),
+                ..param()
+            },
+        ];
+        for param in &invalid {
+            assert!(calc_current_loop_gains(0.5, 0.001, param).is_err());
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::motor::*;
+use anyhow::{bail, Ok, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serialport::SerialPort;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+pub type MotorRef = Arc<Mutex<Motor>>;
+
+/// 命令未指定设备时使用的设备
+pub const DEFAULT_DEVICE: &str = "default";
+
+/// 设备上运行的测试任务, 每个设备各有一套, 不同设备可以同时运行同一种测试
+pub struct DeviceTasks {
+    pub startup_test: Mutex<StartupTestHandle>,
+    pub startup_sweep: Mutex<StartupSweepHandle>,
+    pub spd_autotune: Mutex<SpdAutoTuneHandle>,
+    pub profile_runner: Mutex<ProfileRunner>,
+    pub voltage_protection: Mutex<VoltageProtectionHandle>,
+    pub boot_time: Mutex<BootTimeHandle>,
+    pub endurance_test: Mutex<EnduranceTestHandle>,
+    pub test_plan: Mutex<TestPlanRunner>,
+    pub station: Mutex<StationHandle>,
+}
+
+impl DeviceTasks {
+    fn new() -> Self {
+        DeviceTasks {
+            startup_test: Mutex::new(StartupTestHandle::new()),
+            startup_sweep: Mutex::new(StartupSweepHandle::new()),
+            spd_autotune: Mutex::new(SpdAutoTuneHandle::new()),
+            profile_runner: Mutex::new(ProfileRunner::new()),
+            voltage_protection: Mutex::new(VoltageProtectionHandle::new()),
+            boot_time: Mutex::new(BootTimeHandle::new()),
+            endurance_test: Mutex::new(EnduranceTestHandle::new()),
+            test_plan: Mutex::new(TestPlanRunner::new()),
+            station: Mutex::new(StationHandle::new()),
+        }
+    }
+
+    /// 停止设备上的全部测试, 产线测试先停止, 其内部的测试计划/启停测试随后停止
+    fn stop_all(&self) {
+        self.station.lock().unwrap().stop();
+        self.test_plan.lock().unwrap().stop();
+        self.startup_test.lock().unwrap().stop();
+        self.startup_sweep.lock().unwrap().stop();
+        self.spd_autotune.lock().unwrap().stop();
+        self.profile_runner.lock().unwrap().abort();
+        self.voltage_protection.lock().unwrap().stop();
+        self.boot_time.lock().unwrap().stop();
+        self.endurance_test.lock().unwrap().stop();
+    }
+}
+
+#[derive(Clone)]
+pub struct Device {
+    pub motor: MotorRef,
+    pub tasks: Arc<DeviceTasks>,
+    ramp: Arc<Mutex<Option<Arc<AtomicBool>>>>, // 正在下发的S曲线的运行标志, 停机时清除以中断下发
+    ramp_lock: Arc<Mutex<()>>,                 // 同一设备同时只有一路S曲线下发
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DeviceInfo {
+    pub id: String,
+    pub port_name: Option<String>,
+    pub connected: bool,
+}
+
+impl Device {
+    fn new() -> Self {
+        Device {
+            motor: Arc::new(Mutex::new(Motor::new())),
+            tasks: Arc::new(DeviceTasks::new()),
+            ramp: Arc::new(Mutex::new(None)),
+            ramp_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// 中断设备上正在下发的S曲线
+    pub fn cancel_ramp(&self) {
+        if let Some(running) = self.ramp.lock().unwrap().take() {
+            running.store(false, Ordering::SeqCst);
+        }
+    }
+
+    /// 开始一路S曲线下发: 先中断已有的下发并等待其退出, 返回的锁在本次下发结束前保持
+    pub fn begin_ramp(&self) -> (MutexGuard<'_, ()>, Arc<AtomicBool>) {
+        self.cancel_ramp();
+        let guard = self.ramp_lock.lock().unwrap();
+        let running = Arc::new(AtomicBool::new(true));
+        *self.ramp.lock().unwrap() = Some(Arc::clone(&running));
+        (guard, running)
+    }
+
+    /// 下发结束, 只清除本次下发的运行标志
+    pub fn end_ramp(&self, running: &Arc<AtomicBool>) {
+        let mut ramp = self.ramp.lock().unwrap();
+        if ramp.as_ref().is_some_and(|r| Arc::ptr_eq(r, running)) {
+            *ramp = None;
+        }
+    }
+}
+
+/// 已连接的电机, 以连接 id 为键
+///
+/// 测试任务按设备区分; 继电器 RELAY 和程控电源 POWER_SUPPLY 对应测试台上的同一套硬件, 由所有设备共用
+pub static DEVICES: Lazy<Mutex<HashMap<String, Device>>> = Lazy::new(|| {
+    let mut devices = HashMap::new();
+    devices.insert(DEFAULT_DEVICE.to_string(), Device::new());
+    Mutex::new(devices)
+});
+
+fn resolve_id(id: &Option<String>) -> &str {
+    match id {
+        Some(id) if !id.is_empty() => id.as_str(),
+        _ => DEFAULT_DEVICE,
+    }
+}
+
+pub fn get_device(id: &Option<String>) -> Result<Device> {
+    let id = resolve_id(id);
+    match DEVICES.lock().unwrap().get(id) {
+        Some(device) => Ok(device.clone()),
+        None => bail!("device {} not found", id),
+    }
+}
+
+pub fn get_motor(id: &Option<String>) -> Result<MotorRef> {
+    Ok(get_device(id)?.motor)
+}
+
+/// 为设备绑定串口, 设备不存在时新建
+pub fn open_device(id: &Option<String>, port: Box<dyn SerialPort>) {
+    let id = resolve_id(id).to_string();
+    let device = DEVICES
+        .lock()
+        .unwrap()
+        .entry(id)
+        .or_insert_with(Device::new)
+        .clone();
+
+    device.motor.lock().unwrap().port = Some(port);
+}
+
+/// 释放设备串口, 默认设备之外的设备同时从列表中移除
+pub fn close_device(id: &Option<String>) {
+    let id = resolve_id(id).to_string();
+    let device = if id == DEFAULT_DEVICE {
+        DEVICES.lock().unwrap().get(&id).cloned()
+    } else {
+        DEVICES.lock().unwrap().remove(&id)
+    };
+
+    if let Some(device) = device {
+        // 设备移除后无法再查询或停止其测试, 先停止
+        if id != DEFAULT_DEVICE {
+            device.tasks.stop_all();
+        }
+        device.motor.lock().unwrap().port = None;
+    }
+}
+
+/// 释放占用指定串口的设备, 用于固件升级等需要独占串口的操作
+pub fn release_port(port_name: &str) {
+    let devices: Vec<Device> = DEVICES.lock().unwrap().values().cloned().collect();
+    for device in devices {
+        let mut motor = device.motor.lock().unwrap();
+        if motor
+            .port
+            .as_ref()
+            .is_some_and(|p| p.name().as_deref() == Some(port_name))
+        {
+            motor.port = None;
+        }
+    }
+}
+
+pub fn list_devices() -> Vec<DeviceInfo> {
+    let devices: Vec<(String, Device)> = DEVICES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, device)| (id.clone(), device.clone()))
+        .collect();
+
+    let mut infos: Vec<DeviceInfo> = devices
+        .into_iter()
+        .map(|(id, device)| {
+            let motor = device.motor.lock().unwrap();
+            DeviceInfo {
+                id,
+                port_name: motor.port.as_ref().and_then(|p| p.name()),
+                connected: motor.port.is_some(),
+            }
+        })
+        .collect();
+    infos.sort_by(|a, b| a.id.cmp(&b.id));
+
+    infos
+}
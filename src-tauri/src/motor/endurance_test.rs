@@ -0,0 +1,586 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use crate::tools::switch_power;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn default_sample_interval_ms() -> u32 {
+    1000
+}
+
+fn default_window_s() -> u32 {
+    60
+}
+
+fn default_settle_s() -> u32 {
+    10
+}
+
+fn default_power_on_delay_ms() -> u32 {
+    2000
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 判定阈值, 为空时不检查该项
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct EnduranceThresholds {
+    #[serde(default)]
+    pub max_speed_drift: Option<f32>, // 窗口平均转速与目标转速的最大偏差, rps
+    #[serde(default)]
+    pub max_jitter: Option<f32>, // 窗口内转速标准差上限, rps
+    #[serde(default)]
+    pub max_rs_rise_pct: Option<f32>, // 在线电阻相对首个窗口的最大上升比例, %
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct EnduranceTestParam {
+    pub target_rps: f32,
+    pub duration_s: u64,
+    #[serde(default = "default_sample_interval_ms")]
+    pub sample_interval_ms: u32,
+    #[serde(default = "default_window_s")]
+    pub window_s: u32, // 统计窗口长度
+    #[serde(default = "default_settle_s")]
+    pub settle_s: u32, // 启动后不参与统计的时间
+    #[serde(default)]
+    pub has_relay: bool,
+    #[serde(default = "default_power_on_delay_ms")]
+    pub power_on_delay_ms: u32,
+    #[serde(default = "default_true")]
+    pub rs_online: bool, // 测试期间开启在线电阻辨识
+    #[serde(default = "default_true")]
+    pub stop_on_fail: bool,
+    #[serde(default)]
+    pub thresholds: EnduranceThresholds,
+    #[serde(default)]
+    pub record_path: Option<String>, // 原始采样数据保存路径(csv)
+}
+
+/// 一个统计窗口的结果
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct EnduranceWindow {
+    pub index: u32,
+    pub end_time: String,
+    pub elapsed_s: f32,
+    pub samples: u32,
+    pub mean_rps: f32,
+    pub jitter_rps: f32,
+    pub min_rps: f32,
+    pub max_rps: f32,
+    pub vdc_bus: f64,
+    pub rs_online: Option<f32>,
+    #[serde(default)]
+    pub comm_errors: u32, // 通信失败被丢弃的采样数
+    pub passed: bool,
+    pub reason: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct EnduranceResult {
+    pub running: bool,
+    pub elapsed_s: f32,
+    pub duration_s: u64,
+    pub passed: Option<bool>,
+    pub failures: Vec<String>,
+    pub error_code: Option<u16>,
+    pub fault_bits: String,
+    pub rs_initial: Option<f32>,
+    pub rs_latest: Option<f32>,
+    pub windows: Vec<EnduranceWindow>,
+    #[serde(default)]
+    pub device: Option<DeviceSnapshot>, // 上电后读取的设备信息, 用于 html 报告
+}
+
+#[derive(Debug, Serialize)]
+struct EnduranceSample {
+    elapsed_s: f32,
+    rps: Option<f32>, // 通信失败时为空
+    error_code: u16,
+    motor_state: String,
+    vdc_bus: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct EnduranceReport<'a> {
+    param: &'a Option<EnduranceTestParam>,
+    result: &'a EnduranceResult,
+}
+
+pub struct EnduranceTestHandle {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    result: Arc<Mutex<EnduranceResult>>,
+    test_param: Arc<Mutex<Option<EnduranceTestParam>>>,
+}
+
+impl EnduranceTestHandle {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            result: Arc::new(Mutex::new(EnduranceResult::default())),
+            test_param: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 长时间连续运行测试: 按窗口统计转速漂移、抖动、母线电压和在线电阻, 超出阈值或出现故障判定失败
+    pub fn start(&self, motor: MotorRef, param: EnduranceTestParam) -> Result<()> {
+        if param.sample_interval_ms == 0 || param.window_s == 0 {
+            bail!("sample interval and window must be greater than 0");
+        }
+
+        if param.duration_s == 0 {
+            bail!("duration must be greater than 0");
+        }
+
+        if motor.lock().unwrap().port.is_none() {
+            bail!("motor port is not connected");
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("endurance test is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        let mut recorder = match param.record_path {
+            Some(ref path) if !path.is_empty() => Some(csv::Writer::from_path(path)?),
+            _ => None,
+        };
+
+        *self.test_param.lock().unwrap() = Some(param.clone());
+        *self.result.lock().unwrap() = EnduranceResult {
+            running: true,
+            duration_s: param.duration_s,
+            ..Default::default()
+        };
+
+        let running = Arc::clone(&self.running);
+        let result = Arc::clone(&self.result);
+        running.store(true, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            LOGGER.lock().unwrap().info(
+                format!(
+                    "开始耐久测试: {} rps, {} s",
+                    param.target_rps, param.duration_s
+                )
+                .as_str(),
+            );
+
+            if let Err(err) = Self::run(&motor, &param, &running, &result, &mut recorder) {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .danger(format!("耐久测试执行失败: {}", err).as_str());
+                result.lock().unwrap().failures.push(err.to_string());
+            }
+
+            // 安全停机、断电
+            motor.lock().unwrap().stop_motor().ok();
+            if param.rs_online {
+                motor.lock().unwrap().set_motor_rs_online_enable(false).ok();
+            }
+            if param.has_relay {
+                switch_power(|relay| relay.turn_off()).ok();
+            }
+
+            if let Some(ref mut wtr) = recorder {
+                wtr.flush().ok();
+            }
+
+            let passed = {
+                let mut result = result.lock().unwrap();
+                result.passed = Some(result.failures.is_empty());
+                result.running = false;
+                result.passed.unwrap()
+            };
+
+            if passed {
+                LOGGER.lock().unwrap().info("耐久测试通过");
+            } else {
+                LOGGER.lock().unwrap().danger("耐久测试未通过");
+            }
+
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    fn run(
+        motor: &MotorRef,
+        param: &EnduranceTestParam,
+        running: &AtomicBool,
+        result: &Mutex<EnduranceResult>,
+        recorder: &mut Option<csv::Writer<std::fs::File>>,
+    ) -> Result<()> {
+        // 电机上电
+        if param.has_relay {
+            switch_power(|relay| relay.turn_on())?;
+        }
+        thread::sleep(Duration::from_millis(param.power_on_delay_ms as u64));
+        result.lock().unwrap().device = Some(DeviceSnapshot::capture(motor));
+
+        if param.rs_online {
+            motor.lock().unwrap().set_motor_rs_online_enable(true)?;
+        }
+
+        motor
+            .lock()
+            .unwrap()
+            .update_motor_speed_rps((param.target_rps * 100000.0) as u32)?;
+        thread::sleep(Duration::from_millis(100));
+        motor.lock().unwrap().start_motor()?;
+
+        let start = Instant::now();
+        let interval = Duration::from_millis(param.sample_interval_ms as u64);
+        let window_samples = (param.window_s * 1000).div_ceil(param.sample_interval_ms);
+        let mut window: Vec<(f32, f64)> = Vec::new();
+        let mut window_index = 0;
+        let mut comm_errors = 0;
+
+        while running.load(Ordering::SeqCst) {
+            let elapsed = start.elapsed().as_secs_f32();
+            if elapsed >= param.duration_s as f32 {
+                break;
+            }
+            result.lock().unwrap().elapsed_s = elapsed;
+
+            let status = motor.lock().unwrap().get_motor_status()?;
+            let error_code = status.error_code.unwrap_or_default();
+            let rps = motor.lock().unwrap().get_current_rps()?;
+            // 通信失败时 get_current_rps 返回 -1000, 该采样不计入统计
+            let comm_ok = rps > -1000.0;
+            let vdc_bus = motor
+                .lock()
+                .unwrap()
+                .get_motor_params()?
+                .vdc_bus
+                .unwrap_or_default();
+
+            if let Some(ref mut wtr) = recorder {
+                wtr.serialize(EnduranceSample {
+                    elapsed_s: elapsed,
+                    rps: comm_ok.then_some(rps),
+                    error_code,
+                    motor_state: status.motor_state.clone().unwrap_or_default(),
+                    vdc_bus,
+                })?;
+            }
+
+            // 任何故障直接判定失败
+            if error_code > 0 {
+                let bits = decode_fault_bits(error_code);
+                let mut r = result.lock().unwrap();
+                r.error_code = Some(error_code);
+                r.fault_bits = bits.join("|");
+                r.failures.push(format!(
+                    "fault at {:.0} s: 0x{:x} [{}]",
+                    elapsed,
+                    error_code,
+                    bits.join(", ")
+                ));
+                return Ok(());
+            }
+
+            if !comm_ok {
+                comm_errors += 1;
+            } else if elapsed >= param.settle_s as f32 {
+                window.push((rps, vdc_bus));
+            }
+
+            if window.len() as u32 >= window_samples {
+                window_index += 1;
+                let rs_online = if param.rs_online {
+                    Some(motor.lock().unwrap().get_rs_online()?)
+                } else {
+                    None
+                };
+
+                let mut stats = Self::evaluate_window(
+                    param,
+                    window_index,
+                    elapsed,
+                    &window,
+                    rs_online,
+                    result.lock().unwrap().rs_initial,
+                );
+                stats.comm_errors = comm_errors;
+                window.clear();
+                comm_errors = 0;
+
+                let snapshot = {
+                    let mut r = result.lock().unwrap();
+                    if r.rs_initial.is_none() {
+                        r.rs_initial = rs_online;
+                    }
+                    r.rs_latest = rs_online.or(r.rs_latest);
+                    if !stats.passed {
+                        r.failures
+                            .push(format!("window {}: {}", stats.index, stats.reason));
+                    }
+                    r.windows.push(stats.clone());
+                    r.clone()
+                };
+
+                LOGGER.lock().unwrap().info(
+                    format!(
+                        "第{}个窗口: 平均转速 {:.3} rps, 抖动 {:.3} rps, 母线电压 {:.2} V",
+                        stats.index, stats.mean_rps, stats.jitter_rps, stats.vdc_bus
+                    )
+                    .as_str(),
+                );
+                LOGGER.lock().unwrap().emit("endurance_progress", snapshot);
+
+                if !stats.passed {
+                    LOGGER.lock().unwrap().danger(
+                        format!("第{}个窗口超出阈值: {}", stats.index, stats.reason).as_str(),
+                    );
+                    if param.stop_on_fail {
+                        return Ok(());
+                    }
+                }
+            }
+
+            thread::sleep(interval);
+        }
+
+        // 未运行满测试时长即被停止, 不能判定为通过
+        if !running.load(Ordering::SeqCst) {
+            let elapsed = start.elapsed().as_secs_f32();
+            result
+                .lock()
+                .unwrap()
+                .failures
+                .push(format!("stopped at {:.0} s", elapsed));
+        }
+
+        Ok(())
+    }
+
+    /// 统计一个窗口并与阈值比较
+    fn evaluate_window(
+        param: &EnduranceTestParam,
+        index: u32,
+        elapsed: f32,
+        window: &[(f32, f64)],
+        rs_online: Option<f32>,
+        rs_initial: Option<f32>,
+    ) -> EnduranceWindow {
+        let n = window.len().max(1) as f32;
+        let mean = window.iter().map(|(rps, _)| rps).sum::<f32>() / n;
+        let var = window
+            .iter()
+            .map(|(rps, _)| (rps - mean) * (rps - mean))
+            .sum::<f32>()
+            / n;
+        let jitter = libm::sqrtf(var);
+        let vdc_bus = window.iter().map(|(_, vdc)| vdc).sum::<f64>() / n as f64;
+
+        let thresholds = &param.thresholds;
+        let mut reasons = Vec::new();
+
+        let drift = libm::fabsf(mean - param.target_rps);
+        if thresholds.max_speed_drift.is_some_and(|max| drift > max) {
+            reasons.push(format!("speed drift {:.3} rps", drift));
+        }
+
+        if thresholds.max_jitter.is_some_and(|max| jitter > max) {
+            reasons.push(format!("jitter {:.3} rps", jitter));
+        }
+
+        if let (Some(max), Some(rs), Some(rs0)) =
+            (thresholds.max_rs_rise_pct, rs_online, rs_initial)
+        {
+            if rs0 > 0.0 {
+                let rise = (rs - rs0) / rs0 * 100.0;
+                if rise > max {
+                    reasons.push(format!("rs rise {:.1}%", rise));
+                }
+            }
+        }
+
+        EnduranceWindow {
+            index,
+            end_time: chrono::Local::now().to_rfc3339(),
+            elapsed_s: elapsed,
+            samples: window.len() as u32,
+            mean_rps: mean,
+            jitter_rps: jitter,
+            min_rps: window.iter().map(|(rps, _)| *rps).fold(f32::MAX, f32::min),
+            max_rps: window.iter().map(|(rps, _)| *rps).fold(f32::MIN, f32::max),
+            vdc_bus,
+            rs_online,
+            comm_errors: 0,
+            passed: reasons.is_empty(),
+            reason: if reasons.is_empty() {
+                String::from("passed")
+            } else {
+                reasons.join(", ")
+            },
+        }
+    }
+
+    pub fn stop(&self) {
+        LOGGER.lock().unwrap().warning("停止耐久测试");
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_result(&self) -> Result<EnduranceResult> {
+        Ok(self.result.lock().unwrap().clone())
+    }
+
+    /// 导出测试报告, 根据扩展名选择格式:
+    /// json 包含测试参数和全部结果; csv 只包含各窗口统计, 测试参数另存为同名 yaml;
+    /// html 为包含设备信息、各窗口统计曲线图的独立报告
+    pub fn export_report(&self, path: &str) -> Result<()> {
+        let result = self.result.lock().unwrap();
+        let test_param = self.test_param.lock().unwrap();
+
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match ext.as_str() {
+            "json" => {
+                let report = EnduranceReport {
+                    param: &test_param,
+                    result: &result,
+                };
+                std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+            }
+            "csv" => {
+                let mut wtr = csv::Writer::from_path(path)?;
+                for window in result.windows.iter() {
+                    wtr.serialize(window)?;
+                }
+                wtr.flush()?;
+
+                let yaml = serde_yaml::to_string(&*test_param)?;
+                let param_path = std::path::Path::new(path).with_extension("yaml");
+                crate::tools::save_yaml(yaml, param_path.to_str().unwrap())?;
+            }
+            "html" => {
+                std::fs::write(path, Self::render_html(&test_param, &result)?)?;
+            }
+            _ => bail!("unsupported report format: {}", ext),
+        }
+
+        Ok(())
+    }
+
+    fn render_html(
+        test_param: &Option<EnduranceTestParam>,
+        result: &EnduranceResult,
+    ) -> Result<String> {
+        let mut body = html_device_section(result.device.as_ref());
+
+        body.push_str("<h2>Configuration</h2>\n");
+        body.push_str(&html_kv_table(&serde_json::to_value(test_param)?));
+
+        let summary = serde_json::json!({
+            "passed": result.passed,
+            "elapsed_s": format!("{:.0}", result.elapsed_s),
+            "duration_s": result.duration_s,
+            "windows": result.windows.len(),
+            "error_code": result.error_code,
+            "fault_bits": result.fault_bits,
+            "rs_initial": result.rs_initial,
+            "rs_latest": result.rs_latest,
+            "failures": result.failures.join("; "),
+        });
+        body.push_str("<h2>Summary</h2>\n");
+        body.push_str(&html_kv_table(&summary));
+
+        let target = test_param.as_ref().map(|p| p.target_rps as f64);
+        let windows = &result.windows;
+        body.push_str(&svg_line_chart(
+            "Mean speed",
+            &windows
+                .iter()
+                .map(|w| (w.elapsed_s as f64, w.mean_rps as f64))
+                .collect::<Vec<_>>(),
+            target,
+            "time (s)",
+            "rps",
+        ));
+        body.push_str(&svg_line_chart(
+            "Speed jitter",
+            &windows
+                .iter()
+                .map(|w| (w.elapsed_s as f64, w.jitter_rps as f64))
+                .collect::<Vec<_>>(),
+            None,
+            "time (s)",
+            "rps",
+        ));
+        body.push_str(&svg_line_chart(
+            "Online Rs",
+            &windows
+                .iter()
+                .filter_map(|w| w.rs_online.map(|rs| (w.elapsed_s as f64, rs as f64)))
+                .collect::<Vec<_>>(),
+            None,
+            "time (s)",
+            "ohm",
+        ));
+
+        let rows: Vec<Vec<String>> = windows
+            .iter()
+            .map(|w| {
+                vec![
+                    w.index.to_string(),
+                    html_escape(&w.end_time),
+                    format!("{:.0}", w.elapsed_s),
+                    format!("{:.3}", w.mean_rps),
+                    format!("{:.3}", w.jitter_rps),
+                    format!("{:.3} / {:.3}", w.min_rps, w.max_rps),
+                    format!("{:.2}", w.vdc_bus),
+                    w.rs_online
+                        .map_or(String::from("-"), |v| format!("{:.4}", v)),
+                    w.comm_errors.to_string(),
+                    html_escape(&w.reason),
+                ]
+            })
+            .collect();
+        let fail_rows: Vec<bool> = windows.iter().map(|w| !w.passed).collect();
+        body.push_str("<h2>Windows</h2>\n");
+        body.push_str(&html_table(
+            &[
+                "#",
+                "end time",
+                "elapsed (s)",
+                "mean rps",
+                "jitter",
+                "min / max",
+                "vdc (V)",
+                "rs online",
+                "comm errors",
+                "result",
+            ],
+            &rows,
+            &fail_rows,
+        ));
+
+        Ok(html_page("Endurance Test Report", &body))
+    }
+}
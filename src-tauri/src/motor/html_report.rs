@@ -0,0 +1,293 @@
+use crate::motor::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write;
+
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 240.0;
+const CHART_MARGIN: f64 = 40.0;
+
+const REPORT_STYLE: &str = "body{font-family:Arial,Helvetica,sans-serif;margin:24px;color:#303133}\
+h1{font-size:22px}h2{font-size:17px;margin-top:28px;border-bottom:1px solid #dcdfe6;padding-bottom:4px}\
+table{border-collapse:collapse;font-size:13px;margin:8px 0}\
+th,td{border:1px solid #dcdfe6;padding:4px 8px;text-align:left}th{background:#f5f7fa}\
+tr.fail td{background:#fde2e2}.pass{color:#67c23a;font-weight:bold}.fail-text{color:#f56c6c;font-weight:bold}\
+svg{background:#fff;border:1px solid #ebeef5;margin:6px 0}";
+
+/// 转义 html 特殊字符
+pub fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 完整的 html 页面, 样式内嵌, 不依赖外部资源
+pub fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n<p>Generated: {}</p>\n{}\n</body>\n</html>\n",
+        html_escape(title),
+        REPORT_STYLE,
+        html_escape(title),
+        chrono::Local::now().to_rfc3339(),
+        body
+    )
+}
+
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("-"),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// 将结构体(序列化为 json 对象)渲染为两列的键值表, 嵌套对象的键以 . 连接
+pub fn html_kv_table(value: &Value) -> String {
+    fn flatten(prefix: &str, value: &Value, rows: &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    let key = if prefix.is_empty() {
+                        k.clone()
+                    } else {
+                        format!("{}.{}", prefix, k)
+                    };
+                    flatten(&key, v, rows);
+                }
+            }
+            _ => rows.push((prefix.to_string(), value_to_text(value))),
+        }
+    }
+
+    let mut rows = Vec::new();
+    flatten("", value, &mut rows);
+
+    let mut html = String::from("<table>\n");
+    for (k, v) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            html_escape(&k),
+            html_escape(&v)
+        );
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// 渲染表格, rows 中每行的单元格为已转义的 html; fail_rows 对应的行标红
+pub fn html_table(headers: &[&str], rows: &[Vec<String>], fail_rows: &[bool]) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for h in headers {
+        let _ = write!(html, "<th>{}</th>", html_escape(h));
+    }
+    html.push_str("</tr>\n");
+
+    for (i, row) in rows.iter().enumerate() {
+        if fail_rows.get(i).copied().unwrap_or(false) {
+            html.push_str("<tr class=\"fail\">");
+        } else {
+            html.push_str("<tr>");
+        }
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", cell);
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n");
+    html
+}
+
+/// 折线图(svg), reference 为水平参考线(如目标转速)
+pub fn svg_line_chart(
+    title: &str,
+    points: &[(f64, f64)],
+    reference: Option<f64>,
+    x_label: &str,
+    y_label: &str,
+) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+         <text x=\"{cx}\" y=\"16\" font-size=\"13\" text-anchor=\"middle\">{title}</text>\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        cx = CHART_WIDTH / 2.0,
+        title = html_escape(title)
+    );
+
+    let finite: Vec<(f64, f64)> = points
+        .iter()
+        .copied()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .collect();
+    if finite.is_empty() {
+        svg.push_str("<text x=\"50%\" y=\"50%\" font-size=\"12\" text-anchor=\"middle\">no data</text>\n</svg>\n");
+        return svg;
+    }
+
+    let (mut x_min, mut x_max) = (f64::MAX, f64::MIN);
+    let (mut y_min, mut y_max) = (f64::MAX, f64::MIN);
+    for (x, y) in finite.iter() {
+        x_min = x_min.min(*x);
+        x_max = x_max.max(*x);
+        y_min = y_min.min(*y);
+        y_max = y_max.max(*y);
+    }
+    if let Some(r) = reference {
+        y_min = y_min.min(r);
+        y_max = y_max.max(r);
+    }
+    if x_max - x_min <= f64::EPSILON {
+        x_max = x_min + 1.0;
+    }
+    if y_max - y_min <= f64::EPSILON {
+        y_min -= 0.5;
+        y_max += 0.5;
+    }
+
+    let plot_w = CHART_WIDTH - CHART_MARGIN * 2.0;
+    let plot_h = CHART_HEIGHT - CHART_MARGIN * 2.0;
+    let sx = |x: f64| CHART_MARGIN + (x - x_min) / (x_max - x_min) * plot_w;
+    let sy = |y: f64| CHART_HEIGHT - CHART_MARGIN - (y - y_min) / (y_max - y_min) * plot_h;
+
+    // 坐标轴和刻度
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#c0c4cc\"/>",
+        CHART_MARGIN, CHART_MARGIN, plot_w, plot_h
+    );
+    for i in 0..=4 {
+        let fy = y_min + (y_max - y_min) * i as f64 / 4.0;
+        let fx = x_min + (x_max - x_min) * i as f64 / 4.0;
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{x0}\" y1=\"{y}\" x2=\"{x1}\" y2=\"{y}\" stroke=\"#ebeef5\"/>\
+             <text x=\"{tx}\" y=\"{ty}\" font-size=\"10\" text-anchor=\"end\">{fy:.2}</text>\
+             <text x=\"{lx}\" y=\"{ly}\" font-size=\"10\" text-anchor=\"middle\">{fx:.1}</text>",
+            x0 = CHART_MARGIN,
+            x1 = CHART_WIDTH - CHART_MARGIN,
+            y = sy(fy),
+            tx = CHART_MARGIN - 4.0,
+            ty = sy(fy) + 3.0,
+            lx = sx(fx),
+            ly = CHART_HEIGHT - CHART_MARGIN + 14.0,
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"middle\">{}</text>\
+         <text x=\"12\" y=\"{}\" font-size=\"11\" text-anchor=\"middle\" transform=\"rotate(-90 12 {})\">{}</text>",
+        CHART_WIDTH / 2.0,
+        CHART_HEIGHT - 6.0,
+        html_escape(x_label),
+        CHART_HEIGHT / 2.0,
+        CHART_HEIGHT / 2.0,
+        html_escape(y_label)
+    );
+
+    if let Some(r) = reference {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\" stroke=\"#e6a23c\" stroke-dasharray=\"4 3\"/>",
+            CHART_MARGIN,
+            CHART_WIDTH - CHART_MARGIN,
+            y = sy(r)
+        );
+    }
+
+    let path: Vec<String> = finite
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", sx(*x), sy(*y)))
+        .collect();
+    let _ = writeln!(
+        svg,
+        "<polyline fill=\"none\" stroke=\"#409eff\" stroke-width=\"1.5\" points=\"{}\"/>",
+        path.join(" ")
+    );
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// 测试运行时的设备信息快照, 继电器测试结束后电机已断电, 导出报告时不能再读取电机
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct DeviceSnapshot {
+    pub captured_at: String,
+    pub version: Option<String>,
+    pub params: Option<MotorStaticParams>,
+    pub error: Option<String>, // 读取失败的原因
+}
+
+impl DeviceSnapshot {
+    /// 读取固件版本和辨识参数, 需在电机上电时调用
+    pub fn capture(motor: &MotorRef) -> Self {
+        let mut snapshot = DeviceSnapshot {
+            captured_at: chrono::Local::now().to_rfc3339(),
+            version: None,
+            params: None,
+            error: None,
+        };
+
+        let mut motor = motor.lock().unwrap();
+        if motor.port.is_none() {
+            snapshot.error = Some(String::from("motor not connected"));
+            return snapshot;
+        }
+
+        match motor.get_motor_static_params() {
+            core::result::Result::Ok(params) => {
+                snapshot.version = Some(format!(
+                    "{}.{}.{}{}",
+                    params.main_version.unwrap_or_default(),
+                    params.sub_version.unwrap_or_default(),
+                    params.rev_version.unwrap_or_default(),
+                    params.stage_version.unwrap_or('a')
+                ));
+                snapshot.params = Some(params);
+            }
+            Err(err) => snapshot.error = Some(err.to_string()),
+        }
+
+        snapshot
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.params.is_some()
+    }
+}
+
+/// 报告中的设备信息, 使用测试运行时保存的快照
+pub fn html_device_section(device: Option<&DeviceSnapshot>) -> String {
+    let device = match device {
+        Some(device) => device,
+        None => return String::from("<h2>Device</h2>\n<p>device info not captured</p>\n"),
+    };
+
+    match device.params {
+        Some(ref params) => format!(
+            "<h2>Device</h2>\n<p>Firmware version: {}</p>\n<p>Captured: {}</p>\n{}",
+            html_escape(device.version.as_deref().unwrap_or("-")),
+            html_escape(&device.captured_at),
+            html_kv_table(&serde_json::to_value(params).unwrap_or_default())
+        ),
+        None => format!(
+            "<h2>Device</h2>\n<p>failed to read params: {}</p>\n",
+            html_escape(device.error.as_deref().unwrap_or("-"))
+        ),
+    }
+}
+
+/// 读取遥测 csv 中的转速曲线, 时间单位 s
+pub fn load_telemetry_trace(path: &str) -> Vec<(f64, f64)> {
+    let mut rdr = match csv::Reader::from_path(path) {
+        core::result::Result::Ok(rdr) => rdr,
+        Err(_) => return Vec::new(),
+    };
+
+    rdr.deserialize::<TelemetrySample>()
+        .filter_map(|s| s.ok())
+        .map(|s| (s.time_ms as f64 / 1000.0, s.rps as f64))
+        .collect()
+}
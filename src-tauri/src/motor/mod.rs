@@ -1,7 +1,37 @@
 mod apis;
 mod upgrade;
 mod startup_test;
+mod step_response;
+mod spd_autotune;
+mod current_loop;
+mod scurve;
+mod speed_profile;
+mod telemetry;
+mod startup_sweep;
+mod voltage_protection;
+mod boot_time;
+mod endurance_test;
+mod test_plan;
+mod station;
+mod html_report;
+mod devices;
+mod startup_stations;
 
 pub use self::apis::*;
 pub use self::upgrade::*;
 pub use self::startup_test::*;
+pub use self::step_response::*;
+pub use self::spd_autotune::*;
+pub use self::current_loop::*;
+pub use self::scurve::*;
+pub use self::speed_profile::*;
+pub use self::telemetry::*;
+pub use self::startup_sweep::*;
+pub use self::voltage_protection::*;
+pub use self::boot_time::*;
+pub use self::endurance_test::*;
+pub use self::test_plan::*;
+pub use self::station::*;
+pub use self::html_report::*;
+pub use self::devices::*;
+pub use self::startup_stations::*;
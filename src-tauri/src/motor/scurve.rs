@@ -0,0 +1,181 @@
+use crate::motor::*;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn default_rate_hz() -> u32 {
+    50
+}
+
+/// S曲线加减速参数, 加速度单位 rps/s, 加加速度单位 rps/s²
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct SCurveParam {
+    pub acc_max: f32,
+    pub jerk_max: f32,
+    #[serde(default = "default_rate_hz")]
+    pub rate_hz: u32, // 设定值下发频率
+}
+
+/// 加加速度受限的转速过渡曲线: 加加速 -> 匀加速 -> 减加速
+#[derive(Debug, Clone)]
+pub struct SCurve {
+    v0: f32,
+    dv: f32,
+    dir: f32,
+    jerk: f32,
+    acc: f32,
+    tj: f32,
+    ta: f32,
+}
+
+impl SCurveParam {
+    pub fn validate(&self) -> Result<()> {
+        if self.acc_max <= 0.0 || self.jerk_max <= 0.0 || self.rate_hz == 0 {
+            bail!("acc_max, jerk_max and rate_hz must be greater than 0");
+        }
+        Ok(())
+    }
+}
+
+impl SCurve {
+    pub fn new(from: f32, to: f32, param: &SCurveParam) -> Self {
+        let dv = libm::fabsf(to - from);
+        let jerk = param.jerk_max;
+
+        // 转速变化量不足以达到最大加速度时, 没有匀加速段
+        let (acc, tj, ta) = if dv >= param.acc_max * param.acc_max / jerk {
+            let tj = param.acc_max / jerk;
+            (param.acc_max, tj, dv / param.acc_max - tj)
+        } else {
+            let acc = libm::sqrtf(dv * jerk);
+            (acc, acc / jerk, 0.0)
+        };
+
+        SCurve {
+            v0: from,
+            dv,
+            dir: if to >= from { 1.0 } else { -1.0 },
+            jerk,
+            acc,
+            tj,
+            ta,
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        2.0 * self.tj + self.ta
+    }
+
+    /// 过渡开始 t 秒后的设定转速
+    pub fn sample(&self, t: f32) -> f32 {
+        let total = self.duration();
+        let v = if t <= 0.0 {
+            0.0
+        } else if t < self.tj {
+            self.jerk * t * t / 2.0
+        } else if t < self.tj + self.ta {
+            self.jerk * self.tj * self.tj / 2.0 + self.acc * (t - self.tj)
+        } else if t < total {
+            let tau = total - t;
+            self.dv - self.jerk * tau * tau / 2.0
+        } else {
+            self.dv
+        };
+
+        self.v0 + self.dir * v
+    }
+}
+
+/// 按固定频率向电机下发S曲线设定转速, running 变为 false 时提前返回
+pub fn stream_scurve(
+    motor: &MotorRef,
+    from: f32,
+    to: f32,
+    param: &SCurveParam,
+    running: &AtomicBool,
+) -> Result<()> {
+    param.validate()?;
+
+    let curve = SCurve::new(from, to, param);
+    let interval = Duration::from_secs_f32(1.0 / param.rate_hz as f32);
+    let start = Instant::now();
+
+    loop {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let t = start.elapsed().as_secs_f32();
+        let setpoint = curve.sample(t);
+        motor
+            .lock()
+            .unwrap()
+            .update_motor_speed_rps((setpoint * 100000.0) as u32)?;
+
+        if t >= curve.duration() {
+            break;
+        }
+
+        thread::sleep(interval);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param() -> SCurveParam {
+        SCurveParam {
+            acc_max: 10.0,
+            jerk_max: 20.0,
+            rate_hz: 50,
+        }
+    }
+
+    #[test]
+    fn scurve_with_constant_acceleration() {
+        let curve = SCurve::new(0.0, 20.0, &param());
+
+        // tj = 0.5s, ta = 1.5s
+        assert!((curve.duration() - 2.5).abs() < 1e-5);
+        assert_eq!(curve.sample(0.0), 0.0);
+        assert!((curve.sample(0.5) - 2.5).abs() < 1e-4);
+        assert!((curve.sample(1.25) - 10.0).abs() < 1e-4);
+        assert!((curve.sample(2.0) - 17.5).abs() < 1e-4);
+        assert_eq!(curve.sample(3.0), 20.0);
+    }
+
+    #[test]
+    fn scurve_short_and_descending() {
+        let curve = SCurve::new(30.0, 28.0, &param());
+
+        // 变化量不足, 加速度峰值为 sqrt(2 * 20)
+        assert!((curve.duration() - 2.0 * libm::sqrtf(40.0) / 20.0).abs() < 1e-5);
+        assert!((curve.sample(curve.duration() / 2.0) - 29.0).abs() < 1e-4);
+        assert_eq!(curve.sample(curve.duration()), 28.0);
+    }
+
+    #[test]
+    fn scurve_is_monotonic() {
+        let curve = SCurve::new(5.0, 50.0, &param());
+        let samples: Vec<f32> = (0..=600).map(|i| curve.sample(i as f32 * 0.01)).collect();
+
+        assert!(samples.windows(2).all(|w| w[1] >= w[0] - 1e-4));
+        assert_eq!(*samples.last().unwrap(), 50.0);
+    }
+
+    #[test]
+    fn scurve_param_validate() {
+        assert!(param().validate().is_ok());
+        assert!(SCurveParam {
+            jerk_max: 0.0,
+            ..param()
+        }
+        .validate()
+        .is_err());
+    }
+}
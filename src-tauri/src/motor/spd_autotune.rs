@@ -0,0 +1,302 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn default_max_iterations() -> u32 {
+    8
+}
+
+fn default_max_overshoot_pct() -> f32 {
+    10.0
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct SpdAutoTuneParam {
+    pub step: StepTestParam,
+    pub kp_min: f64,
+    pub kp_max: f64,
+    pub ki_min: f64,
+    pub ki_max: f64,
+    #[serde(default = "default_max_iterations")]
+    pub max_iterations: u32,
+    #[serde(default = "default_max_overshoot_pct")]
+    pub max_overshoot_pct: f32,
+    #[serde(default)]
+    pub save_path: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct SpdAutoTuneResult {
+    pub running: bool,
+    pub iteration: u32,
+    pub original_kp: Option<f64>,
+    pub original_ki: Option<f64>,
+    pub proposed_kp: Option<f64>,
+    pub proposed_ki: Option<f64>,
+    pub before: Option<StepMetrics>,
+    pub after: Option<StepMetrics>,
+    pub applied: bool,
+    pub error: Option<String>,
+}
+
+pub struct SpdAutoTuneHandle {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    result: Arc<Mutex<SpdAutoTuneResult>>,
+    motor: Mutex<Option<MotorRef>>, // 整定所用的电机, 确认写入时使用
+}
+
+impl SpdAutoTuneHandle {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            result: Arc::new(Mutex::new(SpdAutoTuneResult::default())),
+            motor: Mutex::new(None),
+        }
+    }
+
+    /// 启动速度环PI自整定: 以阶跃响应为评价, 在安全范围内对 kp_spd / ki_spd 做坐标搜索
+    pub fn start(&self, motor: MotorRef, param: SpdAutoTuneParam) -> Result<()> {
+        if param.kp_min <= 0.0
+            || param.ki_min <= 0.0
+            || param.kp_min > param.kp_max
+            || param.ki_min > param.ki_max
+        {
+            bail!("invalid kp/ki bounds");
+        }
+
+        // 从静止启动包含开环启动过程, 不能反映速度环特性
+        if param.step.init_rps <= 0.0 {
+            bail!("init rps must be greater than 0");
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("auto tune is running");
+        }
+
+        // 回收上一次已结束的线程
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        let running = Arc::clone(&self.running);
+        let result = Arc::clone(&self.result);
+        *result.lock().unwrap() = SpdAutoTuneResult {
+            running: true,
+            ..Default::default()
+        };
+
+        *self.motor.lock().unwrap() = Some(motor.clone());
+
+        running.store(true, Ordering::SeqCst);
+        let handle = thread::spawn(move || {
+            if let Err(err) = Self::tune(&motor, &param, &running, &result) {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .danger(format!("速度环自整定失败: {}", err).as_str());
+                result.lock().unwrap().error = Some(err.to_string());
+            }
+
+            // 恢复原始参数并停机, 只有确认后才写入推荐参数
+            let (kp, ki) = {
+                let r = result.lock().unwrap();
+                (r.original_kp, r.original_ki)
+            };
+            if let (Some(kp), Some(ki)) = (kp, ki) {
+                write_spd_gains(&motor, kp, ki).ok();
+            }
+            motor.lock().unwrap().update_motor_speed_hz(0).ok();
+            motor.lock().unwrap().stop_motor().ok();
+
+            result.lock().unwrap().running = false;
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_result(&self) -> Result<SpdAutoTuneResult> {
+        Ok(self.result.lock().unwrap().clone())
+    }
+
+    /// 用户确认后写入推荐的 kp_spd / ki_spd
+    pub fn apply(&self) -> Result<()> {
+        if self.running.load(Ordering::SeqCst) {
+            bail!("auto tune is running");
+        }
+
+        let motor = match self.motor.lock().unwrap().clone() {
+            Some(motor) => motor,
+            None => bail!("no proposed gains"),
+        };
+
+        let mut result = self.result.lock().unwrap();
+        match (result.proposed_kp, result.proposed_ki) {
+            (Some(kp), Some(ki)) => {
+                write_spd_gains(&motor, kp, ki)?;
+                result.applied = true;
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .info(format!("已写入速度环参数 kp: {}, ki: {}", kp, ki).as_str());
+            }
+            _ => bail!("no proposed gains"),
+        }
+
+        Ok(())
+    }
+
+    fn tune(
+        motor: &MotorRef,
+        param: &SpdAutoTuneParam,
+        running: &AtomicBool,
+        result: &Mutex<SpdAutoTuneResult>,
+    ) -> Result<()> {
+        let mut step = param.step.clone();
+        step.stop_after = false;
+        step.save_path = None;
+
+        let static_params = motor.lock().unwrap().get_motor_static_params()?;
+        let original_kp = static_params.kp_spd.unwrap_or_default();
+        let original_ki = static_params.ki_spd.unwrap_or_default();
+        {
+            let mut r = result.lock().unwrap();
+            r.original_kp = Some(original_kp);
+            r.original_ki = Some(original_ki);
+        }
+
+        LOGGER.lock().unwrap().info("开始速度环自整定");
+
+        // 原始参数的响应作为基准
+        let before = run_step_test(motor, &step)?.metrics;
+        result.lock().unwrap().before = Some(before.clone());
+
+        let mut best_kp = original_kp.clamp(param.kp_min, param.kp_max);
+        let mut best_ki = original_ki.clamp(param.ki_min, param.ki_max);
+        let mut best_metrics = if best_kp == original_kp && best_ki == original_ki {
+            before.clone()
+        } else {
+            Self::evaluate(motor, &step, best_kp, best_ki)?
+        };
+        let mut best_cost = Self::cost(&step, &best_metrics, param.max_overshoot_pct);
+
+        let mut factor = 1.5;
+        for iteration in 1..=param.max_iterations {
+            if !running.load(Ordering::SeqCst) {
+                bail!("auto tune stopped");
+            }
+            result.lock().unwrap().iteration = iteration;
+
+            let candidates = [
+                (best_kp * factor, best_ki),
+                (best_kp / factor, best_ki),
+                (best_kp, best_ki * factor),
+                (best_kp, best_ki / factor),
+            ];
+
+            let mut improved = false;
+            for (kp, ki) in candidates {
+                if !running.load(Ordering::SeqCst) {
+                    bail!("auto tune stopped");
+                }
+
+                let kp = kp.clamp(param.kp_min, param.kp_max);
+                let ki = ki.clamp(param.ki_min, param.ki_max);
+                if kp == best_kp && ki == best_ki {
+                    continue;
+                }
+
+                let metrics = Self::evaluate(motor, &step, kp, ki)?;
+                let cost = Self::cost(&step, &metrics, param.max_overshoot_pct);
+                if cost < best_cost {
+                    best_kp = kp;
+                    best_ki = ki;
+                    best_cost = cost;
+                    best_metrics = metrics;
+                    improved = true;
+                }
+            }
+
+            LOGGER.lock().unwrap().info(
+                format!(
+                    "自整定第{}轮: kp: {:.6}, ki: {:.6}, 代价: {:.1}",
+                    iteration, best_kp, best_ki, best_cost
+                )
+                .as_str(),
+            );
+
+            // 无改进时缩小搜索步长
+            if !improved {
+                factor = libm::sqrt(factor);
+                if factor < 1.02 {
+                    break;
+                }
+            }
+        }
+
+        {
+            let mut r = result.lock().unwrap();
+            r.proposed_kp = Some(best_kp);
+            r.proposed_ki = Some(best_ki);
+            r.after = Some(best_metrics);
+        }
+
+        LOGGER
+            .lock()
+            .unwrap()
+            .info(format!("自整定完成, 推荐 kp: {:.6}, ki: {:.6}", best_kp, best_ki).as_str());
+
+        if let Some(ref path) = param.save_path {
+            if !path.is_empty() {
+                let yaml = serde_yaml::to_string(&*result.lock().unwrap())?;
+                crate::tools::save_yaml(yaml, path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn evaluate(motor: &MotorRef, step: &StepTestParam, kp: f64, ki: f64) -> Result<StepMetrics> {
+        write_spd_gains(motor, kp, ki)?;
+        Ok(run_step_test(motor, step)?.metrics)
+    }
+
+    /// 代价函数: 调节时间为主, 超调超限和稳态误差加罚
+    fn cost(step: &StepTestParam, metrics: &StepMetrics, max_overshoot_pct: f32) -> f32 {
+        let duration = step.duration_ms as f32;
+        let delta = libm::fabsf(step.target_rps - step.init_rps).max(f32::EPSILON);
+
+        let mut cost = metrics.settling_time_ms.unwrap_or(duration * 2.0);
+        let overshoot = metrics.overshoot_pct.unwrap_or(100.0);
+        if overshoot > max_overshoot_pct {
+            cost += duration * (overshoot - max_overshoot_pct) / 10.0;
+        }
+        cost += duration * libm::fabsf(metrics.steady_state_error.unwrap_or(delta)) / delta;
+
+        cost
+    }
+}
+
+fn write_spd_gains(motor: &MotorRef, kp: f64, ki: f64) -> Result<()> {
+    let mut motor = motor.lock().unwrap();
+    motor.update_motor_kp_spd((kp * 100000000.0) as u32)?;
+    motor.update_motor_ki_spd((ki * 100000000.0) as u32)?;
+    Ok(())
+}
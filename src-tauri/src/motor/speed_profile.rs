@@ -0,0 +1,475 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use crate::tools;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 转速曲线中的一个设定点: 在 time 秒时开始用 ramp 秒过渡到 rps
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ProfilePoint {
+    pub time: f32,
+    pub rps: f32,
+    #[serde(default)]
+    pub ramp: f32,
+}
+
+fn default_update_interval_ms() -> u32 {
+    100
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct ProfileRunParam {
+    pub path: String,
+    #[serde(default)]
+    pub looping: bool,
+    #[serde(default)]
+    pub loop_count: Option<u32>, // 循环次数, 为空时无限循环
+    #[serde(default = "default_update_interval_ms")]
+    pub update_interval_ms: u32,
+    #[serde(default)]
+    pub record_path: Option<String>,
+    #[serde(default)]
+    pub scurve: Option<SCurveParam>, // 设置后各点之间按S曲线过渡, 忽略 ramp
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct ProfileStatus {
+    pub state: String,
+    pub step: usize,
+    pub total_steps: usize,
+    pub loop_index: u32,
+    pub elapsed: f32,
+    pub setpoint: f32,
+    pub rps: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct ProfileRecord {
+    elapsed: f32,
+    loop_index: u32,
+    step: usize,
+    setpoint: f32,
+    rps: f32,
+    vdc_bus: f64,
+}
+
+pub struct ProfileRunner {
+    running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    status: Arc<Mutex<ProfileStatus>>,
+}
+
+/// 读取转速曲线文件, 支持 csv(表头: time,rps,ramp) 和 yaml(设定点列表)
+pub fn load_speed_profile(path: &str) -> Result<Vec<ProfilePoint>> {
+    if !tools::is_file_exist(path) {
+        bail!("File: {} not exist", path)
+    }
+
+    let ext = std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let points: Vec<ProfilePoint> = match ext.as_str() {
+        "csv" => {
+            let mut rdr = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_path(path)?;
+            rdr.deserialize().collect::<std::result::Result<_, _>>()?
+        }
+        "yaml" | "yml" => tools::read_yaml(path)?,
+        _ => bail!("unsupported profile format: {}", ext),
+    };
+
+    if points.is_empty() {
+        bail!("profile is empty");
+    }
+
+    for (i, p) in points.iter().enumerate() {
+        if p.time < 0.0 || p.rps < 0.0 || p.ramp < 0.0 {
+            bail!("invalid point at step {}", i + 1);
+        }
+        if i > 0 && p.time < points[i - 1].time {
+            bail!("points must be sorted by time, step {}", i + 1);
+        }
+    }
+
+    Ok(points)
+}
+
+/// 计算曲线在 t 秒时的设定转速, 返回 (所在步, 设定转速)
+///
+/// start_rps 为本轮开始时的转速: 首轮为 0, 循环时为上一轮最后一点的转速, 避免设定值跳变
+fn profile_setpoint(
+    points: &[ProfilePoint],
+    scurve: Option<&SCurveParam>,
+    start_rps: f32,
+    t: f32,
+) -> (usize, f32) {
+    let step = match points.iter().rposition(|p| p.time <= t) {
+        Some(idx) => idx,
+        None => return (0, start_rps),
+    };
+
+    let point = &points[step];
+    let prev = if step > 0 {
+        points[step - 1].rps
+    } else {
+        start_rps
+    };
+    let dt = t - point.time;
+
+    if let Some(param) = scurve {
+        return (step, SCurve::new(prev, point.rps, param).sample(dt));
+    }
+
+    if point.ramp > 0.0 && dt < point.ramp {
+        (step, prev + (point.rps - prev) * dt / point.ramp)
+    } else {
+        (step, point.rps)
+    }
+}
+
+fn profile_duration(points: &[ProfilePoint], scurve: Option<&SCurveParam>, start_rps: f32) -> f32 {
+    let mut prev = start_rps;
+    let mut duration: f32 = 0.0;
+    for p in points {
+        let transition = match scurve {
+            Some(param) => SCurve::new(prev, p.rps, param).duration(),
+            None => p.ramp,
+        };
+        duration = duration.max(p.time + transition);
+        prev = p.rps;
+    }
+    duration
+}
+
+impl ProfileRunner {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            status: Arc::new(Mutex::new(ProfileStatus::default())),
+        }
+    }
+
+    pub fn start(&self, motor: MotorRef, param: ProfileRunParam) -> Result<()> {
+        let points = load_speed_profile(&param.path)?;
+
+        if param.update_interval_ms == 0 {
+            bail!("update interval must be greater than 0");
+        }
+
+        if let Some(ref scurve) = param.scurve {
+            scurve.validate()?;
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("speed profile is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        let mut recorder = match param.record_path {
+            Some(ref path) if !path.is_empty() => Some(csv::Writer::from_path(path)?),
+            _ => None,
+        };
+
+        let running = Arc::clone(&self.running);
+        let paused = Arc::clone(&self.paused);
+        let status = Arc::clone(&self.status);
+
+        *status.lock().unwrap() = ProfileStatus {
+            state: String::from("running"),
+            total_steps: points.len(),
+            ..Default::default()
+        };
+
+        running.store(true, Ordering::SeqCst);
+        paused.store(false, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            LOGGER
+                .lock()
+                .unwrap()
+                .info(format!("开始执行转速曲线: {}", param.path).as_str());
+
+            let state = match Self::run(
+                &motor,
+                &param,
+                &points,
+                &running,
+                &paused,
+                &status,
+                &mut recorder,
+            ) {
+                core::result::Result::Ok(true) => "finished",
+                core::result::Result::Ok(false) => "aborted",
+                Err(err) => {
+                    LOGGER
+                        .lock()
+                        .unwrap()
+                        .danger(format!("转速曲线执行失败: {}", err).as_str());
+                    "aborted"
+                }
+            };
+
+            // 安全停机
+            motor.lock().unwrap().update_motor_speed_hz(0).ok();
+            motor.lock().unwrap().stop_motor().ok();
+
+            if let Some(ref mut wtr) = recorder {
+                wtr.flush().ok();
+            }
+
+            let status = {
+                let mut status = status.lock().unwrap();
+                status.state = String::from(state);
+                status.clone()
+            };
+            LOGGER.lock().unwrap().emit("profile_progress", status);
+            LOGGER
+                .lock()
+                .unwrap()
+                .warning(format!("转速曲线执行结束: {}", state).as_str());
+
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    /// 返回 true 表示曲线正常执行完成, false 表示被中止
+    fn run(
+        motor: &MotorRef,
+        param: &ProfileRunParam,
+        points: &[ProfilePoint],
+        running: &AtomicBool,
+        paused: &AtomicBool,
+        status: &Mutex<ProfileStatus>,
+        recorder: &mut Option<csv::Writer<std::fs::File>>,
+    ) -> Result<bool> {
+        let interval = Duration::from_millis(param.update_interval_ms as u64);
+        let mut started = false;
+        let mut loop_index = 0;
+
+        loop {
+            let start_rps = if loop_index > 0 {
+                points[points.len() - 1].rps
+            } else {
+                0.0
+            };
+            let duration = profile_duration(points, param.scurve.as_ref(), start_rps);
+            let mut elapsed = 0.0;
+            let mut last_tick = Instant::now();
+            let mut last_step = usize::MAX;
+
+            while elapsed <= duration {
+                if !running.load(Ordering::SeqCst) {
+                    return Ok(false);
+                }
+
+                // 暂停时保持当前设定转速, 曲线时间不推进
+                if !paused.load(Ordering::SeqCst) {
+                    elapsed += last_tick.elapsed().as_secs_f32();
+                }
+                last_tick = Instant::now();
+
+                let (step, setpoint) = profile_setpoint(
+                    points,
+                    param.scurve.as_ref(),
+                    start_rps,
+                    elapsed.min(duration),
+                );
+
+                if !paused.load(Ordering::SeqCst) {
+                    motor
+                        .lock()
+                        .unwrap()
+                        .update_motor_speed_rps((setpoint * 100000.0) as u32)?;
+
+                    if !started && setpoint > 0.0 {
+                        motor.lock().unwrap().start_motor()?;
+                        started = true;
+                    }
+                }
+
+                if step != last_step {
+                    LOGGER.lock().unwrap().info(
+                        format!(
+                            "第{}轮 第{}/{}步: {} rps",
+                            loop_index + 1,
+                            step + 1,
+                            points.len(),
+                            points[step].rps
+                        )
+                        .as_str(),
+                    );
+                    last_step = step;
+                }
+
+                let rps = motor.lock().unwrap().get_current_rps()?;
+
+                if let Some(ref mut wtr) = recorder {
+                    let vdc_bus = motor
+                        .lock()
+                        .unwrap()
+                        .get_motor_params()?
+                        .vdc_bus
+                        .unwrap_or_default();
+                    wtr.serialize(ProfileRecord {
+                        elapsed,
+                        loop_index,
+                        step,
+                        setpoint,
+                        rps,
+                        vdc_bus,
+                    })?;
+                }
+
+                let snapshot = {
+                    let mut status = status.lock().unwrap();
+                    status.state = if paused.load(Ordering::SeqCst) {
+                        String::from("paused")
+                    } else {
+                        String::from("running")
+                    };
+                    status.step = step;
+                    status.loop_index = loop_index;
+                    status.elapsed = elapsed;
+                    status.setpoint = setpoint;
+                    status.rps = rps;
+                    status.clone()
+                };
+                LOGGER.lock().unwrap().emit("profile_progress", snapshot);
+
+                if elapsed >= duration {
+                    break;
+                }
+
+                thread::sleep(interval);
+            }
+
+            loop_index += 1;
+            if !param.looping {
+                break;
+            }
+            if let Some(count) = param.loop_count {
+                if loop_index >= count {
+                    break;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn pause(&self) {
+        if self.running.load(Ordering::SeqCst) {
+            self.paused.store(true, Ordering::SeqCst);
+            LOGGER.lock().unwrap().warning("转速曲线已暂停");
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.running.load(Ordering::SeqCst) {
+            self.paused.store(false, Ordering::SeqCst);
+            LOGGER.lock().unwrap().info("转速曲线已恢复");
+        }
+    }
+
+    pub fn abort(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.paused.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_status(&self) -> Result<ProfileStatus> {
+        Ok(self.status.lock().unwrap().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time: f32, rps: f32, ramp: f32) -> ProfilePoint {
+        ProfilePoint { time, rps, ramp }
+    }
+
+    fn points() -> Vec<ProfilePoint> {
+        vec![
+            point(1.0, 10.0, 2.0),
+            point(5.0, 30.0, 0.0),
+            point(8.0, 20.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn setpoint_with_linear_ramp() {
+        let points = points();
+
+        assert_eq!(profile_setpoint(&points, None, 0.0, 0.5), (0, 0.0));
+        assert_eq!(profile_setpoint(&points, None, 0.0, 2.0), (0, 5.0));
+        assert_eq!(profile_setpoint(&points, None, 0.0, 4.0), (0, 10.0));
+        assert_eq!(profile_setpoint(&points, None, 0.0, 5.0), (1, 30.0));
+        assert_eq!(profile_setpoint(&points, None, 0.0, 8.5), (2, 25.0));
+        assert_eq!(profile_setpoint(&points, None, 0.0, 20.0), (2, 20.0));
+    }
+
+    #[test]
+    fn setpoint_continues_from_previous_loop() {
+        let points = points();
+
+        // 循环开始时保持上一轮最后的转速, 再从该转速过渡到第一点
+        assert_eq!(profile_setpoint(&points, None, 20.0, 0.0), (0, 20.0));
+        assert_eq!(profile_setpoint(&points, None, 20.0, 0.5), (0, 20.0));
+        assert_eq!(profile_setpoint(&points, None, 20.0, 2.0), (0, 15.0));
+
+        let scurve = SCurveParam {
+            acc_max: 10.0,
+            jerk_max: 20.0,
+            rate_hz: 50,
+        };
+        assert_eq!(
+            profile_setpoint(&points, Some(&scurve), 20.0, 1.0),
+            (0, 20.0)
+        );
+    }
+
+    #[test]
+    fn duration_with_ramp_and_scurve() {
+        let points = points();
+        assert_eq!(profile_duration(&points, None, 0.0), 9.0);
+        assert_eq!(profile_duration(&points, None, 20.0), 9.0);
+
+        let scurve = SCurveParam {
+            acc_max: 10.0,
+            jerk_max: 20.0,
+            rate_hz: 50,
+        };
+        let last = SCurve::new(30.0, 20.0, &scurve).duration();
+        assert_eq!(profile_duration(&points, Some(&scurve), 0.0), 8.0 + last);
+
+        // 首个过渡的时长取决于起始转速
+        let single = [point(0.0, 20.0, 0.0)];
+        let from_zero = SCurve::new(0.0, 20.0, &scurve).duration();
+        assert_eq!(profile_duration(&single, Some(&scurve), 0.0), from_zero);
+        assert_eq!(profile_duration(&single, Some(&scurve), 20.0), 0.0);
+    }
+}
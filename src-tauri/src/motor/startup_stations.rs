@@ -0,0 +1,191 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use anyhow::{bail, Ok, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// 单个工位的测试参数, 工位以电机连接 id 区分
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StartupStationParam {
+    pub device_id: String,
+    pub test_param: StartUpTestParam,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StartupStationStatus {
+    pub device_id: String,
+    pub running: bool,
+    pub total_cnt: u32,
+    pub result: TestResult,
+    pub error: Option<String>, // 启动失败的原因
+}
+
+/// 所有工位的汇总进度
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct StartupStationsSummary {
+    pub running: bool,
+    pub total_cnt: u32,
+    pub success_cnt: u32,
+    pub failed_cnt: u32,
+    pub progress: f32,
+    pub stations: Vec<StartupStationStatus>,
+}
+
+/// 多工位启动测试, 每个工位有独立的测试线程、计数和测试记录
+pub struct StartupStations {
+    stations: Mutex<HashMap<String, Arc<StartupTestHandle>>>,
+    errors: Mutex<HashMap<String, String>>,
+}
+
+pub static STARTUPSTATIONS: Lazy<Mutex<StartupStations>> =
+    Lazy::new(|| Mutex::new(StartupStations::new()));
+
+impl StartupStations {
+    pub fn new() -> Self {
+        Self {
+            stations: Mutex::new(HashMap::new()),
+            errors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 同时启动多个工位的测试, 单个工位启动失败不影响其他工位
+    pub fn start(&self, params: Vec<StartupStationParam>) -> Result<()> {
+        if params.is_empty() {
+            bail!("no station to test");
+        }
+
+        // 检查工位和继电器通道是否重复, 共用通道会导致一个工位断电时其他工位也被断电
+        let mut device_ids = HashSet::new();
+        let mut channels = HashSet::new();
+        for param in params.iter() {
+            if !device_ids.insert(param.device_id.clone()) {
+                bail!("duplicate station {}", param.device_id);
+            }
+
+            if param.test_param.has_relay && params.len() > 1 {
+                match param.test_param.relay_channel {
+                    Some(channel) => {
+                        if !channels.insert(channel) {
+                            bail!("relay channel {} is used by more than one station", channel);
+                        }
+                    }
+                    None => bail!("station {} has no relay channel", param.device_id),
+                }
+            }
+        }
+
+        let mut stations = self.stations.lock().unwrap();
+        if stations.values().any(|s| s.is_running()) {
+            bail!("startup test is running");
+        }
+
+        stations.clear();
+        self.errors.lock().unwrap().clear();
+
+        for param in params {
+            let device_id = param.device_id.clone();
+            let mut test_param = param.test_param;
+            if test_param.station_name.is_none() {
+                test_param.station_name = Some(device_id.clone());
+            }
+
+            let station = Arc::new(StartupTestHandle::new());
+            let started = get_motor(&Some(device_id.clone())).and_then(|motor| {
+                if motor.lock().unwrap().port.is_none() {
+                    bail!("motor port is not connected");
+                }
+                station.start(motor, test_param)
+            });
+
+            if let Err(err) = started {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .danger(format!("[{}] 启动测试失败: {}", device_id, err).as_str());
+                self.errors
+                    .lock()
+                    .unwrap()
+                    .insert(device_id.clone(), err.to_string());
+            }
+
+            stations.insert(device_id, station);
+        }
+
+        Ok(())
+    }
+
+    /// 停止所有工位, 先通知全部工位再等待线程退出, 各工位同时停止
+    pub fn stop(&self) {
+        let stations: Vec<Arc<StartupTestHandle>> =
+            self.stations.lock().unwrap().values().cloned().collect();
+
+        for station in stations.iter() {
+            station.request_stop();
+        }
+        for station in stations.iter() {
+            station.stop();
+        }
+    }
+
+    pub fn stop_station(&self, device_id: &str) -> Result<()> {
+        self.get_station(device_id)?.stop();
+        Ok(())
+    }
+
+    pub fn pause_station(&self, device_id: &str) -> Result<()> {
+        self.get_station(device_id)?.pause();
+        Ok(())
+    }
+
+    pub fn resume_station(&self, device_id: &str) -> Result<()> {
+        self.get_station(device_id)?.resume();
+        Ok(())
+    }
+
+    pub fn get_station(&self, device_id: &str) -> Result<Arc<StartupTestHandle>> {
+        match self.stations.lock().unwrap().get(device_id) {
+            Some(station) => Ok(Arc::clone(station)),
+            None => bail!("station {} not found", device_id),
+        }
+    }
+
+    pub fn get_summary(&self) -> Result<StartupStationsSummary> {
+        let mut stations: Vec<(String, Arc<StartupTestHandle>)> = self
+            .stations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, station)| (id.clone(), Arc::clone(station)))
+            .collect();
+        stations.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let errors = self.errors.lock().unwrap().clone();
+        let mut summary = StartupStationsSummary::default();
+        for (device_id, station) in stations {
+            let result = station.get_test_result()?;
+            let total_cnt = station.get_total_count();
+            let running = station.is_running();
+
+            summary.running |= running;
+            summary.total_cnt += total_cnt;
+            summary.success_cnt += result.success_cnt.unwrap_or_default();
+            summary.failed_cnt += result.failed_cnt.unwrap_or_default();
+            summary.stations.push(StartupStationStatus {
+                error: errors.get(&device_id).cloned(),
+                device_id,
+                running,
+                total_cnt,
+                result,
+            });
+        }
+
+        if summary.total_cnt > 0 {
+            summary.progress =
+                (summary.success_cnt + summary.failed_cnt) as f32 / summary.total_cnt as f32;
+        }
+
+        Ok(summary)
+    }
+}
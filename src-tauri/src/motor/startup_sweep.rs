@@ -0,0 +1,495 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// 一组待测试的启动参数
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StartupCombo {
+    pub align_current: f32,
+    pub startup_current: f32,
+    pub speed_force: f32,
+    pub speed_start: f32,
+}
+
+/// 网格扫描的取值列表, 某一项为空时使用电机当前值
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct StartupSweepGrid {
+    #[serde(default)]
+    pub align_current: Vec<f32>,
+    #[serde(default)]
+    pub startup_current: Vec<f32>,
+    #[serde(default)]
+    pub speed_force: Vec<f32>,
+    #[serde(default)]
+    pub speed_start: Vec<f32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StartupSweepParam {
+    pub test_param: StartUpTestParam,
+    pub trials_per_combo: u32,
+    #[serde(default)]
+    pub grid: Option<StartupSweepGrid>,
+    #[serde(default)]
+    pub combos: Option<Vec<StartupCombo>>, // 列表模式, 优先于 grid
+    #[serde(default)]
+    pub save_path: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StartupSweepRow {
+    pub align_current: f32,
+    pub startup_current: f32,
+    pub speed_force: f32,
+    pub speed_start: f32,
+    pub trials: u32,
+    pub success_cnt: u32,
+    pub success_rate: f32,
+    pub mean_time_to_target_ms: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct StartupSweepResult {
+    pub running: bool,
+    pub combo_index: usize,
+    pub total_combos: usize,
+    pub trial_index: u32,
+    pub rows: Vec<StartupSweepRow>,
+    pub best: Option<usize>,
+    pub applied: bool,
+    pub error: Option<String>, // 扫描被中止的原因, 如继电器未确认
+}
+
+pub struct StartupSweepHandle {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    result: Arc<Mutex<StartupSweepResult>>,
+    original: Arc<Mutex<Option<MotorStartupParams>>>,
+    motor: Mutex<Option<MotorRef>>, // 扫描所用的电机, 确认写入时使用
+    test_param: Mutex<Option<StartUpTestParam>>, // 确认写入时按该配置控制继电器
+}
+
+fn combo_to_params(base: &MotorStartupParams, combo: &StartupCombo) -> MotorStartupParams {
+    MotorStartupParams {
+        flux_current: base.flux_current,
+        align_current: Some(combo.align_current),
+        startup_current: Some(combo.startup_current),
+        torque_current: base.torque_current,
+        speed_start: Some(combo.speed_start),
+        speed_force: Some(combo.speed_force),
+    }
+}
+
+/// 展开网格为参数组合列表
+fn expand_grid(grid: &StartupSweepGrid, base: &MotorStartupParams) -> Vec<StartupCombo> {
+    let or_base = |values: &Vec<f32>, base: Option<f32>| {
+        if values.is_empty() {
+            vec![base.unwrap_or_default()]
+        } else {
+            values.clone()
+        }
+    };
+
+    let mut combos = Vec::new();
+    for align_current in or_base(&grid.align_current, base.align_current) {
+        for startup_current in or_base(&grid.startup_current, base.startup_current) {
+            for speed_force in or_base(&grid.speed_force, base.speed_force) {
+                for speed_start in or_base(&grid.speed_start, base.speed_start) {
+                    combos.push(StartupCombo {
+                        align_current,
+                        startup_current,
+                        speed_force,
+                        speed_start,
+                    });
+                }
+            }
+        }
+    }
+
+    combos
+}
+
+impl StartupSweepHandle {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            result: Arc::new(Mutex::new(StartupSweepResult::default())),
+            original: Arc::new(Mutex::new(None)),
+            motor: Mutex::new(None),
+            test_param: Mutex::new(None),
+        }
+    }
+
+    /// 启动参数扫描: 对每组启动参数执行 N 次启动测试, 统计成功率和平均达速时间
+    pub fn start(&self, motor: MotorRef, param: StartupSweepParam) -> Result<()> {
+        if param.trials_per_combo == 0 {
+            bail!("trials per combo must be greater than 0");
+        }
+
+        if motor.lock().unwrap().port.is_none() {
+            bail!("motor port is not connected");
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("startup sweep is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        match (&param.combos, &param.grid) {
+            (Some(combos), _) if combos.is_empty() => bail!("no startup parameter combination"),
+            (None, None) => bail!("no startup parameter combination"),
+            _ => {}
+        }
+
+        *self.original.lock().unwrap() = None;
+        *self.motor.lock().unwrap() = Some(motor.clone());
+        *self.test_param.lock().unwrap() = Some(param.test_param.clone());
+        *self.result.lock().unwrap() = StartupSweepResult {
+            running: true,
+            ..Default::default()
+        };
+
+        let running = Arc::clone(&self.running);
+        let result = Arc::clone(&self.result);
+        let original = Arc::clone(&self.original);
+        running.store(true, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            // 有继电器时电机此时未上电, 上电后再读取原始启动参数
+            let base = match Self::read_original(&motor, &param.test_param, &running) {
+                core::result::Result::Ok(base) => base,
+                Err(err) => {
+                    LOGGER
+                        .lock()
+                        .unwrap()
+                        .danger(format!("读取启动参数失败: {}", err).as_str());
+                    let mut r = result.lock().unwrap();
+                    r.error = Some(err.to_string());
+                    r.running = false;
+                    running.store(false, Ordering::SeqCst);
+                    return;
+                }
+            };
+            *original.lock().unwrap() = Some(base.clone());
+
+            let combos = match param.combos {
+                Some(ref combos) => combos.clone(),
+                None => expand_grid(param.grid.as_ref().unwrap(), &base),
+            };
+            result.lock().unwrap().total_combos = combos.len();
+
+            LOGGER
+                .lock()
+                .unwrap()
+                .info(format!("开始启动参数扫描, 共{}组", combos.len()).as_str());
+
+            for (idx, combo) in combos.iter().enumerate() {
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                result.lock().unwrap().combo_index = idx;
+                LOGGER.lock().unwrap().info(
+                    format!(
+                        "第{}组: align: {}, startup: {}, force: {}, start: {}",
+                        idx + 1,
+                        combo.align_current,
+                        combo.startup_current,
+                        combo.speed_force,
+                        combo.speed_start
+                    )
+                    .as_str(),
+                );
+
+                let params = combo_to_params(&base, combo);
+                let mut trials = 0;
+                let mut success_cnt = 0;
+                let mut times = Vec::new();
+
+                for trial in 1..=param.trials_per_combo {
+                    result.lock().unwrap().trial_index = trial;
+
+                    let record = StartupTestHandle::run_trial(
+                        &motor,
+                        &param.test_param,
+                        trial,
+                        &running,
+                        Some(&params),
+                    )
+                    .and_then(|record| {
+                        StartupTestHandle::power_off_and_cool(&param.test_param, &running)?;
+                        Ok(record)
+                    });
+
+                    let record = match record {
+                        core::result::Result::Ok(Some(record)) => record,
+                        core::result::Result::Ok(None) => break,
+                        Err(err) => {
+                            LOGGER
+                                .lock()
+                                .unwrap()
+                                .danger(format!("启动参数扫描中止: {}", err).as_str());
+                            result.lock().unwrap().error = Some(err.to_string());
+                            running.store(false, Ordering::SeqCst);
+                            break;
+                        }
+                    };
+
+                    trials += 1;
+                    if record.passed {
+                        success_cnt += 1;
+                        if let Some(ms) = record.time_to_target_ms {
+                            times.push(ms as f64);
+                        }
+                    }
+                }
+
+                if trials == 0 {
+                    break;
+                }
+
+                let row = StartupSweepRow {
+                    align_current: combo.align_current,
+                    startup_current: combo.startup_current,
+                    speed_force: combo.speed_force,
+                    speed_start: combo.speed_start,
+                    trials,
+                    success_cnt,
+                    success_rate: success_cnt as f32 / trials as f32,
+                    mean_time_to_target_ms: if times.is_empty() {
+                        None
+                    } else {
+                        Some(times.iter().sum::<f64>() / times.len() as f64)
+                    },
+                };
+
+                let mut r = result.lock().unwrap();
+                r.rows.push(row);
+                r.best = Self::find_best(&r.rows);
+            }
+
+            // 扫描结束后恢复原始启动参数, 最优参数需用户确认后写入
+            if let Err(err) = Self::write_params(&motor, &param.test_param, base) {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .danger(format!("恢复原始启动参数失败: {}", err).as_str());
+                let mut r = result.lock().unwrap();
+                let message = format!("failed to restore original startup params: {}", err);
+                r.error = Some(match r.error.take() {
+                    Some(prev) => format!("{}; {}", prev, message),
+                    None => message,
+                });
+            }
+
+            if let Some(ref path) = param.save_path {
+                if !path.is_empty() {
+                    if let Err(err) = Self::save_table(&result.lock().unwrap().rows, path) {
+                        LOGGER
+                            .lock()
+                            .unwrap()
+                            .warning(format!("保存扫描结果失败: {}", err).as_str());
+                    }
+                }
+            }
+
+            LOGGER.lock().unwrap().warning("启动参数扫描结束");
+            result.lock().unwrap().running = false;
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    /// 读写启动参数时通信失败不会报错, 先确认控制器应答
+    fn check_responding(motor: &MotorRef) -> Result<()> {
+        if motor.lock().unwrap().probe_version().is_none() {
+            bail!("motor not responding");
+        }
+        Ok(())
+    }
+
+    /// 读取扫描前的启动参数, 有继电器时先上电, 读取后断电冷却
+    fn read_original(
+        motor: &MotorRef,
+        test_param: &StartUpTestParam,
+        running: &AtomicBool,
+    ) -> Result<MotorStartupParams> {
+        if test_param.has_relay {
+            StartupTestHandle::power_on(motor, test_param, running)?;
+        }
+
+        let base = Self::check_responding(motor)
+            .and_then(|_| motor.lock().unwrap().get_motor_startup_params());
+
+        if test_param.has_relay {
+            StartupTestHandle::power_off_and_cool(test_param, running)?;
+        }
+
+        base
+    }
+
+    /// 写入启动参数, 有继电器时电机在两次测试之间处于断电状态, 先上电写入后再断电
+    fn write_params(
+        motor: &MotorRef,
+        test_param: &StartUpTestParam,
+        params: MotorStartupParams,
+    ) -> Result<()> {
+        if test_param.has_relay {
+            // 扫描被停止后也要等待上电完成
+            StartupTestHandle::power_on(motor, test_param, &AtomicBool::new(true))?;
+        }
+
+        let written = Self::check_responding(motor)
+            .and_then(|_| motor.lock().unwrap().update_motor_startup_params(params));
+
+        if test_param.has_relay {
+            StartupTestHandle::relay_power(test_param, false)?;
+        }
+
+        written
+    }
+
+    /// 成功率最高的组合, 成功率相同时取平均达速时间最短的
+    fn find_best(rows: &[StartupSweepRow]) -> Option<usize> {
+        let time = |row: &StartupSweepRow| row.mean_time_to_target_ms.unwrap_or(f64::MAX);
+        rows.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| {
+                a.success_rate
+                    .total_cmp(&b.success_rate)
+                    .then(time(b).total_cmp(&time(a)))
+            })
+            .map(|(idx, _)| idx)
+    }
+
+    fn save_table(rows: &[StartupSweepRow], path: &str) -> Result<()> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        for row in rows {
+            wtr.serialize(row)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_result(&self) -> Result<StartupSweepResult> {
+        Ok(self.result.lock().unwrap().clone())
+    }
+
+    /// 用户确认后写入最优启动参数
+    pub fn apply_best(&self) -> Result<()> {
+        if self.running.load(Ordering::SeqCst) {
+            bail!("startup sweep is running");
+        }
+
+        let mut result = self.result.lock().unwrap();
+        let row = match result.best {
+            Some(idx) => result.rows[idx].clone(),
+            None => bail!("no sweep result"),
+        };
+
+        let base = match *self.original.lock().unwrap() {
+            Some(ref base) => base.clone(),
+            None => bail!("no sweep result"),
+        };
+        let motor = match self.motor.lock().unwrap().clone() {
+            Some(motor) => motor,
+            None => bail!("no sweep result"),
+        };
+        let test_param = match self.test_param.lock().unwrap().clone() {
+            Some(test_param) => test_param,
+            None => bail!("no sweep result"),
+        };
+
+        let combo = StartupCombo {
+            align_current: row.align_current,
+            startup_current: row.startup_current,
+            speed_force: row.speed_force,
+            speed_start: row.speed_start,
+        };
+        Self::write_params(&motor, &test_param, combo_to_params(&base, &combo))?;
+        result.applied = true;
+
+        LOGGER.lock().unwrap().info(
+            format!(
+                "已写入启动参数 align: {}, startup: {}, force: {}, start: {}",
+                combo.align_current, combo.startup_current, combo.speed_force, combo.speed_start
+            )
+            .as_str(),
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_grid_uses_base_for_empty_axes() {
+        let base = MotorStartupParams {
+            flux_current: None,
+            align_current: Some(1.0),
+            startup_current: Some(2.0),
+            torque_current: None,
+            speed_start: None,
+            speed_force: Some(5.0),
+        };
+        let grid = StartupSweepGrid {
+            align_current: vec![0.5, 1.5],
+            speed_start: vec![3.0, 4.0, 6.0],
+            ..Default::default()
+        };
+
+        let combos = expand_grid(&grid, &base);
+        assert_eq!(combos.len(), 6);
+        assert_eq!(
+            combos[0],
+            StartupCombo {
+                align_current: 0.5,
+                startup_current: 2.0,
+                speed_force: 5.0,
+                speed_start: 3.0,
+            }
+        );
+        assert_eq!(combos[5].align_current, 1.5);
+        assert_eq!(combos[5].speed_start, 6.0);
+    }
+
+    #[test]
+    fn expand_empty_grid_is_base() {
+        let base = MotorStartupParams {
+            flux_current: None,
+            align_current: None,
+            startup_current: Some(2.0),
+            torque_current: None,
+            speed_start: Some(3.0),
+            speed_force: Some(5.0),
+        };
+
+        let combos = expand_grid(&StartupSweepGrid::default(), &base);
+        assert_eq!(combos.len(), 1);
+        assert_eq!(combos[0].align_current, 0.0);
+        assert_eq!(combo_to_params(&base, &combos[0]).speed_force, Some(5.0));
+    }
+}
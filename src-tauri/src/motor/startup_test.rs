@@ -1,9 +1,8 @@
 use crate::logger::LOGGER;
 use crate::motor::*;
-use crate::tools::RELAY;
-use anyhow::{Ok, Result};
+use crate::tools::{switch_power, POWER_SUPPLY};
+use anyhow::{bail, Ok, Result};
 use libm;
-use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -18,204 +17,963 @@ enum TestStatus {
     RotatSuccess, // 达到目标转速
 }
 
+/// 单次采样得到的电机状态
+struct TrialSample {
+    status: TestStatus,
+    rps: Option<f32>,
+    error_code: Option<u16>,
+    motor_state: Option<String>,
+}
+
+/// 转速容差类型: 绝对值(rps) 或 目标转速的百分比
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ToleranceMode {
+    #[default]
+    Absolute,
+    Percent,
+}
+
+fn default_tolerance() -> f32 {
+    0.5
+}
+
+fn default_sample_interval_ms() -> u32 {
+    1000
+}
+
+fn default_power_on_delay_ms() -> u32 {
+    2000
+}
+
+fn default_checkpoint_every() -> u32 {
+    1
+}
+
+/// 一帧遥测需要约 10 次请求, 默认间隔不宜小于单帧的采集时间
+fn default_capture_interval_ms() -> u32 {
+    50
+}
+
+fn default_capture_buffer_len() -> usize {
+    3000
+}
+
+/// 启动过程遥测采集参数, 失败的测试(以及按比例抽样的通过测试)会保存到 dir 目录
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TelemetryCaptureParam {
+    dir: String,
+    #[serde(default = "default_capture_interval_ms")]
+    interval_ms: u32,
+    #[serde(default = "default_capture_buffer_len")]
+    buffer_len: usize,
+    #[serde(default)]
+    pass_sample_every: u32, // 每N次通过的测试保存一次, 0 表示只保存失败的测试
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct StartUpTestParam {
     target_rps: f32,
     total_count: u32,
     rotate_duration: u32,
     cold_duration: u32,
-    has_relay: bool,
+    pub has_relay: bool,
+    #[serde(default = "default_tolerance")]
+    tolerance: f32,
+    #[serde(default)]
+    tolerance_mode: ToleranceMode,
+    #[serde(default = "default_sample_interval_ms")]
+    sample_interval_ms: u32,
+    #[serde(default = "default_power_on_delay_ms")]
+    power_on_delay_ms: u32, // 继电器上电后等待电机上电完成的时间
+    #[serde(default)]
+    wait_for_boot: bool, // 上电后等待控制器应答即开始测试, power_on_delay_ms 作为超时时间
+    #[serde(default)]
+    start_timeout_ms: Option<u32>, // 为空时为 rotate_duration 的两倍
+    #[serde(default)]
+    stable_duration_ms: Option<u32>, // 达到目标转速后需要保持的时间, 为空时为 rotate_duration
+    #[serde(default)]
+    capture: Option<TelemetryCaptureParam>,
+    #[serde(default)]
+    checkpoint_path: Option<String>, // 测试进度保存路径, 程序异常退出后可从此文件恢复测试
+    #[serde(default = "default_checkpoint_every")]
+    checkpoint_every: u32, // 每完成N次测试保存一次进度
+    #[serde(default)]
+    pub relay_channel: Option<u8>, // 只控制该路继电器, 为空时控制全部通道
+    #[serde(default)]
+    pub station_name: Option<String>, // 多工位测试时的工位名, 用于区分日志
+    #[serde(default)]
+    pub supply_voltage: Option<f64>, // 测试前将程控电源设置为该电压, 用于降压测试
+    #[serde(default)]
+    pub supply_current_limit: Option<f64>,
+}
+
+impl StartUpTestParam {
+    fn log_tag(&self) -> String {
+        match self.station_name {
+            Some(ref name) if !name.is_empty() => format!("[{}] ", name),
+            _ => String::new(),
+        }
+    }
+
+    fn tolerance_rps(&self) -> f32 {
+        match self.tolerance_mode {
+            ToleranceMode::Absolute => self.tolerance,
+            ToleranceMode::Percent => libm::fabsf(self.target_rps) * self.tolerance / 100.0,
+        }
+    }
+
+    fn timeout_ms(&self) -> u128 {
+        match self.start_timeout_ms {
+            Some(ms) => ms as u128,
+            None => (self.rotate_duration * 1000 * 2) as u128,
+        }
+    }
+
+    /// 判定通过所需的达到目标转速的采样次数, 稳定时间为 0 时至少需要一次
+    fn stable_samples(&self) -> u32 {
+        let stable_ms = self
+            .stable_duration_ms
+            .unwrap_or(self.rotate_duration * 1000);
+        stable_ms.div_ceil(self.sample_interval_ms).max(1)
+    }
+}
+
+/// 启动失败分类
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    Fault,              // 电机报故障
+    StuckInState,       // 停留在对位/开环等启动阶段
+    TimeoutBelowTarget, // 已闭环运行但始终未达到目标转速
+    UnstableAtTarget,   // 曾达到目标转速但超调或振荡, 未能保持稳定
+    CommError,          // 通信失败
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct FailureCounts {
+    pub fault: u32,
+    pub stuck_in_state: u32,
+    pub timeout_below_target: u32,
+    pub unstable_at_target: u32,
+    pub comm_error: u32,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct TestResult {
-    progress: Option<f32>,
-    failed_cnt: Option<u32>,
-    success_cnt: Option<u32>,
+    pub progress: Option<f32>,
+    pub failed_cnt: Option<u32>,
+    pub success_cnt: Option<u32>,
+    pub failure_counts: Option<FailureCounts>,
+    pub paused: Option<bool>,
+    pub error: Option<String>, // 测试被中止的原因, 如继电器未确认
+}
+
+/// 单次启动测试的记录
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TrialRecord {
+    pub index: u32,
+    pub start_time: String,
+    pub time_to_target_ms: Option<u64>,
+    pub final_rps: Option<f32>,
+    pub vdc_bus: Option<f64>,
+    pub error_code: Option<u16>,
+    pub motor_state: Option<String>,
+    pub passed: bool,
+    pub reason: String,
+    pub category: Option<FailureCategory>,
+    pub fault_bits: String,    // 故障位名称, 以 | 分隔
+    pub state_history: String, // 电机状态变化过程, 以 > 分隔
+    pub telemetry_file: Option<String>,
+    #[serde(default)]
+    pub supply_current_peak: Option<f64>, // 程控电源测得的启动过程峰值电流
+}
+
+/// 测试进度, 保存已完成的测试次数、计数和每次测试记录
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StartupTestCheckpoint {
+    pub param: StartUpTestParam,
+    pub completed: u32,
+    pub success_cnt: u32,
+    pub failed_cnt: u32,
+    pub records: Vec<TrialRecord>,
+    #[serde(default)]
+    pub device: Option<DeviceSnapshot>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+struct StartupTestReport<'a> {
+    param: &'a Option<StartUpTestParam>,
+    result: TestResult,
+    trials: &'a Vec<TrialRecord>,
 }
 
 pub struct StartupTestHandle {
     running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
     handle: Mutex<Option<thread::JoinHandle<()>>>,
     total_cnt: Arc<Mutex<u32>>,
     failed_cnt: Arc<Mutex<u32>>,
     success_cnt: Arc<Mutex<u32>>,
+    records: Arc<Mutex<Vec<TrialRecord>>>,
+    error: Arc<Mutex<Option<String>>>,
+    test_param: Arc<Mutex<Option<StartUpTestParam>>>,
+    device: Arc<Mutex<Option<DeviceSnapshot>>>, // 测试时读取的设备信息, 用于 html 报告
 }
 
-pub static STARTUPTEST: Lazy<Mutex<StartupTestHandle>> =
-    Lazy::new(|| Mutex::new(StartupTestHandle::new()));
-
 impl StartupTestHandle {
     pub fn new() -> Self {
         Self {
             running: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
             handle: Mutex::new(None),
             total_cnt: Arc::new(Mutex::new(0)),
             failed_cnt: Arc::new(Mutex::new(0)),
             success_cnt: Arc::new(Mutex::new(0)),
+            records: Arc::new(Mutex::new(Vec::new())),
+            error: Arc::new(Mutex::new(None)),
+            test_param: Arc::new(Mutex::new(None)),
+            device: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn start(&self, motor: MotorRef, test_param: StartUpTestParam) -> Result<()> {
+        Self::validate_param(&test_param)?;
+
+        let checkpoint = StartupTestCheckpoint {
+            param: test_param,
+            completed: 0,
+            success_cnt: 0,
+            failed_cnt: 0,
+            records: Vec::new(),
+            device: None,
+            updated_at: chrono::Local::now().to_rfc3339(),
+        };
+
+        self.run_from(motor, checkpoint)
+    }
+
+    /// 从进度文件恢复测试, 从最后一次完成的测试之后继续
+    pub fn resume_from_checkpoint(&self, motor: MotorRef, path: &str) -> Result<()> {
+        let mut checkpoint = load_startup_checkpoint(path)?;
+        Self::validate_param(&checkpoint.param)?;
+
+        if checkpoint.completed >= checkpoint.param.total_count {
+            bail!("test in checkpoint is already finished");
+        }
+
+        // 未指定进度保存路径时继续写回原文件
+        if checkpoint.param.checkpoint_path.is_none() {
+            checkpoint.param.checkpoint_path = Some(path.to_string());
+        }
+
+        LOGGER.lock().unwrap().info(
+            format!(
+                "从第{}次测试恢复, 已通过{}次, 失败{}次",
+                checkpoint.completed + 1,
+                checkpoint.success_cnt,
+                checkpoint.failed_cnt
+            )
+            .as_str(),
+        );
+
+        self.run_from(motor, checkpoint)
+    }
+
+    fn validate_param(test_param: &StartUpTestParam) -> Result<()> {
+        if test_param.sample_interval_ms == 0 {
+            bail!("sample interval must be greater than 0");
+        }
+
+        if test_param.tolerance < 0.0 {
+            bail!("tolerance must not be negative");
         }
+
+        if let Some(ref capture) = test_param.capture {
+            if capture.interval_ms == 0 {
+                bail!("capture interval must be greater than 0");
+            }
+            std::fs::create_dir_all(&capture.dir)?;
+        }
+
+        Ok(())
     }
 
-    pub fn start(&self, test_param: StartUpTestParam) {
+    fn run_from(&self, motor: MotorRef, checkpoint: StartupTestCheckpoint) -> Result<()> {
         let running = Arc::clone(&self.running);
+        let paused = Arc::clone(&self.paused);
         let mut handle_guard = self.handle.lock().unwrap();
         let total_cnt = Arc::clone(&self.total_cnt);
         let failed_cnt = Arc::clone(&self.failed_cnt);
         let success_cnt = Arc::clone(&self.success_cnt);
+        let records = Arc::clone(&self.records);
+        let error = Arc::clone(&self.error);
+        let device = Arc::clone(&self.device);
 
-        // 重置计数
+        if running.load(Ordering::SeqCst) {
+            bail!("startup test is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        let test_param = checkpoint.param;
+
+        // 设置程控电源的输出电压和限流
+        if test_param.supply_voltage.is_some() || test_param.supply_current_limit.is_some() {
+            let mut supply = POWER_SUPPLY.lock().unwrap();
+            if let Some(current) = test_param.supply_current_limit {
+                supply.set_current_limit(current)?;
+            }
+            if let Some(voltage) = test_param.supply_voltage {
+                supply.set_voltage(voltage)?;
+            }
+        }
+
+        // 重置计数, 恢复测试时使用进度文件中的计数
         *total_cnt.lock().unwrap() = test_param.total_count;
-        *failed_cnt.lock().unwrap() = 0;
-        *success_cnt.lock().unwrap() = 0;
+        *failed_cnt.lock().unwrap() = checkpoint.failed_cnt;
+        *success_cnt.lock().unwrap() = checkpoint.success_cnt;
+        *records.lock().unwrap() = checkpoint.records;
+        *error.lock().unwrap() = None;
+        *self.test_param.lock().unwrap() = Some(test_param.clone());
+        *device.lock().unwrap() = checkpoint.device;
 
         // logger::log_message(app, message, level);
         // LOGGER.lock().unwrap().log_message("开始测试", "info");
 
-        if handle_guard.is_none() {
-            running.store(true, Ordering::SeqCst);
+        running.store(true, Ordering::SeqCst);
+        paused.store(false, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            let mut test_cnt = checkpoint.completed;
+            while running.load(Ordering::SeqCst) {
+                {
+                    test_cnt += 1;
 
-            let handle = thread::spawn(move || {
-                let mut test_cnt = 0;
-                let mut reached_cnt = 0; // 达到目标转速计数
-                while running.load(Ordering::SeqCst) {
+                    let mut fatal = None;
+                    let record =
+                        match Self::run_trial(&motor, &test_param, test_cnt, &running, None) {
+                            std::result::Result::Ok(record) => record,
+                            Err(err) => {
+                                fatal = Some(err);
+                                None
+                            }
+                        };
+
+                    // 本次测试结束时电机仍处于上电状态, 在断电前记录设备信息
+                    if !device
+                        .lock()
+                        .unwrap()
+                        .as_ref()
+                        .is_some_and(|d| d.is_complete())
                     {
-                        // 电机上电
-                        if test_param.has_relay {
-                            RELAY.lock().unwrap().turn_on().unwrap();
+                        *device.lock().unwrap() = Some(DeviceSnapshot::capture(&motor));
+                    }
+
+                    if let Some(record) = record {
+                        if record.passed {
+                            *success_cnt.lock().unwrap() += 1;
+                        } else {
+                            *failed_cnt.lock().unwrap() += 1;
+                        }
+                        records.lock().unwrap().push(record);
+
+                        let every = test_param.checkpoint_every.max(1);
+                        if test_cnt % every == 0 || test_cnt >= test_param.total_count {
+                            Self::save_checkpoint(
+                                &test_param,
+                                test_cnt,
+                                *success_cnt.lock().unwrap(),
+                                *failed_cnt.lock().unwrap(),
+                                &records.lock().unwrap(),
+                                &device.lock().unwrap(),
+                            );
                         }
+                    }
 
-                        // 等待电机上电完成
-                        std::thread::sleep(std::time::Duration::from_millis(2000));
+                    if fatal.is_none() {
+                        fatal = Self::power_off_and_cool(&test_param, &running).err();
+                    }
 
-                        // 启动电机
-                        MOTOR
+                    // 继电器未确认时电机的供电状态未知, 不能继续测试
+                    if let Some(err) = fatal {
+                        LOGGER
                             .lock()
                             .unwrap()
-                            .update_motor_speed_rps((test_param.target_rps * 100000.0) as u32)
-                            .unwrap();
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                        MOTOR.lock().unwrap().start_motor().unwrap();
-
-                        test_cnt += 1;
-                        let start = Instant::now();
+                            .danger(format!("{}测试中止: {}", test_param.log_tag(), err).as_str());
+                        *error.lock().unwrap() = Some(err.to_string());
+                        break;
+                    }
 
+                    if test_cnt >= test_param.total_count {
                         LOGGER
                             .lock()
                             .unwrap()
-                            .info(format!("开始第{}次测试", test_cnt).as_str());
-
-                        loop {
-                            let status = Self::get_test_status(&test_param.target_rps).unwrap();
-                            match status {
-                                TestStatus::Rotating => {}
-                                TestStatus::RotatSuccess => {
-                                    reached_cnt += 1;
-                                } // 启动成功
-                                _ => {
-                                    reached_cnt = 0;
-                                    *failed_cnt.lock().unwrap() += 1;
-                                    LOGGER
-                                        .lock()
-                                        .unwrap()
-                                        .danger(format!("第{}次测试未通过", test_cnt).as_str());
-                                    break;
-                                } // 启动失败
-                            }
+                            .warning(format!("{}测试完成", test_param.log_tag()).as_str());
+                        break;
+                    }
+
+                    // 暂停在两次测试之间生效, 此时电机已断电
+                    if paused.load(Ordering::SeqCst) {
+                        LOGGER.lock().unwrap().warning(
+                            format!("{}测试已暂停, 已完成{}次", test_param.log_tag(), test_cnt)
+                                .as_str(),
+                        );
+                        while paused.load(Ordering::SeqCst) && running.load(Ordering::SeqCst) {
+                            thread::sleep(std::time::Duration::from_millis(100));
+                        }
+                    }
+                }
+            }
 
-                            // 需要连续n秒钟都达到目标转速才判定为测试通过
-                            if reached_cnt >= test_param.rotate_duration {
-                                reached_cnt = 0;
-                                *success_cnt.lock().unwrap() += 1;
-                                LOGGER
-                                    .lock()
-                                    .unwrap()
-                                    .info(format!("第{}次测试通过", test_cnt).as_str());
+            paused.store(false, Ordering::SeqCst);
+            running.store(false, Ordering::SeqCst);
+        });
 
-                                break;
-                            }
+        *handle_guard = Some(handle);
 
-                            if !running.load(Ordering::SeqCst) {
-                                break;
-                            }
+        Ok(())
+    }
 
-                            std::thread::sleep(std::time::Duration::from_millis(1000));
-
-                            // 启动超时则判定为启动失败
-                            if start.elapsed().as_millis() > (test_param.rotate_duration * 1000 * 2) as u128 {
-                                reached_cnt = 0;
-                                *failed_cnt.lock().unwrap() += 1;
-                                LOGGER
-                                    .lock()
-                                    .unwrap()
-                                    .danger(format!("第{}次测试超时", test_cnt).as_str());
-                                break;
-                            }
-                        }
+    /// 保存测试进度, 先写临时文件再重命名, 避免写入过程中崩溃导致进度文件损坏
+    fn save_checkpoint(
+        test_param: &StartUpTestParam,
+        completed: u32,
+        success_cnt: u32,
+        failed_cnt: u32,
+        records: &[TrialRecord],
+        device: &Option<DeviceSnapshot>,
+    ) {
+        let path = match test_param.checkpoint_path {
+            Some(ref path) if !path.is_empty() => path,
+            _ => return,
+        };
 
-                        MOTOR.lock().unwrap().stop_motor().unwrap();
+        let checkpoint = StartupTestCheckpoint {
+            param: test_param.clone(),
+            completed,
+            success_cnt,
+            failed_cnt,
+            records: records.to_vec(),
+            device: device.clone(),
+            updated_at: chrono::Local::now().to_rfc3339(),
+        };
 
-                        // 电机断电
-                        if test_param.has_relay {
-                            RELAY.lock().unwrap().turn_off().unwrap();
-                        }
+        let tmp_path = format!("{}.tmp", path);
+        let saved = serde_json::to_string(&checkpoint)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(std::fs::write(&tmp_path, json)?))
+            .and_then(|_| Ok(std::fs::rename(&tmp_path, path)?));
 
-                        // 等待电机停止转动并冷却
-                        let start = Instant::now();
-                        while running.load(Ordering::SeqCst) {
-                            thread::sleep(std::time::Duration::from_millis(100));
-                            if start.elapsed().as_millis() > (1000 * test_param.cold_duration) as u128 {
-                                break;
-                            }
-                        }
+        if let Err(err) = saved {
+            LOGGER
+                .lock()
+                .unwrap()
+                .warning(format!("保存测试进度失败: {}", err).as_str());
+        }
+    }
+
+    pub fn pause(&self) {
+        if self.running.load(Ordering::SeqCst) {
+            self.paused.store(true, Ordering::SeqCst);
+            LOGGER.lock().unwrap().warning("本次测试完成后暂停");
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.running.load(Ordering::SeqCst) && self.paused.load(Ordering::SeqCst) {
+            self.paused.store(false, Ordering::SeqCst);
+            LOGGER.lock().unwrap().info("测试已恢复");
+        }
+    }
+
+    /// 电机断电并等待电机停止转动、冷却, 继电器未确认断电时返回错误
+    pub(crate) fn power_off_and_cool(
+        test_param: &StartUpTestParam,
+        running: &AtomicBool,
+    ) -> Result<()> {
+        // 电机断电
+        if test_param.has_relay {
+            if let Err(err) = Self::relay_power(test_param, false) {
+                bail!("relay did not confirm power off: {}", err);
+            }
+        }
+
+        // 等待电机停止转动并冷却
+        let start = Instant::now();
+        while running.load(Ordering::SeqCst) {
+            thread::sleep(std::time::Duration::from_millis(100));
+            if start.elapsed().as_millis() > (1000 * test_param.cold_duration) as u128 {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 电机上电并等待控制器启动完成
+    pub(crate) fn power_on(
+        motor: &MotorRef,
+        test_param: &StartUpTestParam,
+        running: &AtomicBool,
+    ) -> Result<()> {
+        if test_param.has_relay {
+            if let Err(err) = Self::relay_power(test_param, true) {
+                bail!("relay did not confirm power on: {}", err);
+            }
+        }
+        let powered_on = Instant::now();
+
+        // 等待电机上电完成, 未应答时继续执行, 由启动电机时的通信失败判定本次测试失败
+        if test_param.wait_for_boot {
+            if let Some((ms, _)) = wait_for_answer(
+                motor,
+                &powered_on,
+                test_param.power_on_delay_ms as u64,
+                20,
+                running,
+            ) {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .info(format!("{}上电{}ms后控制器应答", test_param.log_tag(), ms).as_str());
+            }
+        } else {
+            std::thread::sleep(std::time::Duration::from_millis(
+                test_param.power_on_delay_ms as u64,
+            ));
+        }
 
-                        if test_cnt >= test_param.total_count {
-                            LOGGER.lock().unwrap().warning(format!("测试完成").as_str());
-                            break;
+        Ok(())
+    }
+
+    /// 继电器上电/断电, 指定了通道时只控制该通道
+    pub(crate) fn relay_power(test_param: &StartUpTestParam, on: bool) -> Result<()> {
+        switch_power(|relay| match (test_param.relay_channel, on) {
+            (Some(channel), on) => relay.set_channel(channel, on),
+            (None, true) => relay.turn_on(),
+            (None, false) => relay.turn_off(),
+        })
+    }
+
+    /// 写入启动参数、目标转速并启动电机
+    fn start_trial_motor(
+        motor: &MotorRef,
+        test_param: &StartUpTestParam,
+        startup_params: Option<&MotorStartupParams>,
+    ) -> Result<()> {
+        // 写入命令在无应答时也返回成功, 先确认控制器在线
+        if motor.lock().unwrap().probe_version().is_none() {
+            bail!("motor not responding");
+        }
+
+        if let Some(params) = startup_params {
+            motor
+                .lock()
+                .unwrap()
+                .update_motor_startup_params(params.clone())?;
+        }
+
+        motor
+            .lock()
+            .unwrap()
+            .update_motor_speed_rps((test_param.target_rps * 100000.0) as u32)?;
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        motor.lock().unwrap().start_motor()?;
+
+        Ok(())
+    }
+
+    /// 上电或启动阶段通信失败时的测试记录
+    fn comm_failed_record(
+        test_param: &StartUpTestParam,
+        test_cnt: u32,
+        reason: String,
+    ) -> TrialRecord {
+        LOGGER.lock().unwrap().danger(
+            format!(
+                "{}第{}次测试未通过: {}",
+                test_param.log_tag(),
+                test_cnt,
+                reason
+            )
+            .as_str(),
+        );
+
+        TrialRecord {
+            index: test_cnt,
+            start_time: chrono::Local::now().to_rfc3339(),
+            time_to_target_ms: None,
+            final_rps: None,
+            vdc_bus: None,
+            error_code: None,
+            motor_state: None,
+            passed: false,
+            reason,
+            category: Some(FailureCategory::CommError),
+            fault_bits: String::new(),
+            state_history: String::new(),
+            telemetry_file: None,
+            supply_current_peak: None,
+        }
+    }
+
+    /// 执行一次上电-启动-判定-停机流程, 测试被中止时返回 None
+    ///
+    /// startup_params 不为空时在上电后、启动前写入电机启动参数;
+    /// 继电器未确认上电时返回错误, 此时应中止整个测试
+    pub(crate) fn run_trial(
+        motor: &MotorRef,
+        test_param: &StartUpTestParam,
+        test_cnt: u32,
+        running: &AtomicBool,
+        startup_params: Option<&MotorStartupParams>,
+    ) -> Result<Option<TrialRecord>> {
+        Self::power_on(motor, test_param, running)?;
+
+        // 启动电机, 通信失败只判定本次测试失败, 不影响其他工位
+        if let Err(err) = Self::start_trial_motor(motor, test_param, startup_params) {
+            motor.lock().unwrap().stop_motor().ok();
+            return Ok(Some(Self::comm_failed_record(
+                test_param,
+                test_cnt,
+                format!("communication failed: {}", err),
+            )));
+        }
+
+        let start_time = chrono::Local::now().to_rfc3339();
+        let start = Instant::now();
+
+        LOGGER
+            .lock()
+            .unwrap()
+            .info(format!("{}开始第{}次测试", test_param.log_tag(), test_cnt).as_str());
+
+        let mut record = TrialRecord {
+            index: test_cnt,
+            start_time,
+            time_to_target_ms: None,
+            final_rps: None,
+            vdc_bus: None,
+            error_code: None,
+            motor_state: None,
+            passed: false,
+            reason: String::new(),
+            category: None,
+            fault_bits: String::new(),
+            state_history: String::new(),
+            telemetry_file: None,
+            supply_current_peak: None,
+        };
+
+        let mut ring = test_param
+            .capture
+            .as_ref()
+            .map(|c| TelemetryRing::new(c.buffer_len));
+
+        let tolerance = test_param.tolerance_rps();
+        let mut states: Vec<String> = Vec::new();
+        let mut max_rps: Option<f32> = None;
+        let mut reached_cnt = 0; // 连续达到目标转速的采样计数
+        let mut aborted = false;
+        loop {
+            let sample = Self::get_test_status(motor, &test_param.target_rps, tolerance).unwrap();
+            record.final_rps = sample.rps.or(record.final_rps);
+            record.error_code = sample.error_code.or(record.error_code);
+            record.motor_state = sample.motor_state.or(record.motor_state.take());
+
+            // 记录状态变化过程
+            if let Some(ref state) = record.motor_state {
+                if states.last() != Some(state) {
+                    states.push(state.clone());
+                }
+            }
+            if let Some(rps) = sample.rps {
+                max_rps = Some(max_rps.map_or(rps, |m| m.max(rps)));
+            }
+
+            // 连接了程控电源时记录启动过程的峰值电流
+            let supply_current = {
+                let mut supply = POWER_SUPPLY.lock().unwrap();
+                if supply.is_connected() {
+                    supply.measure_current().ok()
+                } else {
+                    None
+                }
+            };
+            if let Some(current) = supply_current {
+                record.supply_current_peak = Some(
+                    record
+                        .supply_current_peak
+                        .map_or(current, |m| m.max(current)),
+                );
+            }
+
+            match sample.status {
+                TestStatus::Rotating => reached_cnt = 0, // 离开目标转速后重新计时
+                TestStatus::RotatSuccess => {
+                    reached_cnt += 1;
+                    if record.time_to_target_ms.is_none() {
+                        record.time_to_target_ms = Some(start.elapsed().as_millis() as u64);
+                    }
+                } // 启动成功
+                _ => {
+                    match sample.error_code {
+                        Some(code) if code > 0 => {
+                            let bits = decode_fault_bits(code);
+                            record.category = Some(FailureCategory::Fault);
+                            record.reason = format!("fault: 0x{:x} [{}]", code, bits.join(", "));
+                            record.fault_bits = bits.join("|");
+                        }
+                        _ => {
+                            record.category = Some(FailureCategory::CommError);
+                            record.reason = String::from("communication failed");
                         }
                     }
-                }
-            });
+                    LOGGER.lock().unwrap().danger(
+                        format!("{}第{}次测试未通过", test_param.log_tag(), test_cnt).as_str(),
+                    );
+                    break;
+                } // 启动失败
+            }
+
+            // 需要连续n秒钟都达到目标转速才判定为测试通过
+            if reached_cnt >= test_param.stable_samples() {
+                record.passed = true;
+                record.reason = String::from("passed");
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .info(format!("{}第{}次测试通过", test_param.log_tag(), test_cnt).as_str());
+
+                break;
+            }
+
+            if !running.load(Ordering::SeqCst) {
+                aborted = true;
+                break;
+            }
+
+            Self::wait_and_capture(
+                motor,
+                test_param.sample_interval_ms,
+                test_param.capture.as_ref(),
+                ring.as_mut(),
+                &start,
+            );
+
+            // 启动超时则判定为启动失败
+            if start.elapsed().as_millis() > test_param.timeout_ms() {
+                let category = Self::classify_timeout(
+                    &states,
+                    record.time_to_target_ms.is_some(),
+                    max_rps,
+                    test_param.target_rps + tolerance,
+                );
+                record.reason = match category {
+                    FailureCategory::StuckInState => format!(
+                        "timeout: stuck in {}",
+                        states.last().map(|s| s.as_str()).unwrap_or("unknown")
+                    ),
+                    FailureCategory::UnstableAtTarget => {
+                        String::from("timeout: unstable at target")
+                    }
+                    _ => String::from("timeout: below target"),
+                };
+                record.category = Some(category);
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .danger(format!("{}第{}次测试超时", test_param.log_tag(), test_cnt).as_str());
+                break;
+            }
+        }
+
+        // 判定失败时补采一帧, 保证保存的遥测数据包含失败时刻的状态
+        if let (false, false, Some(ring)) = (record.passed, aborted, ring.as_mut()) {
+            let time_ms = start.elapsed().as_millis() as u64;
+            if let std::result::Result::Ok(sample) =
+                motor.lock().unwrap().get_motor_telemetry(time_ms)
+            {
+                ring.push(sample);
+            }
+        }
+
+        if let std::result::Result::Ok(params) = motor.lock().unwrap().get_motor_params() {
+            record.vdc_bus = params.vdc_bus;
+        }
+
+        motor.lock().unwrap().stop_motor().ok();
+
+        record.state_history = states.join(">");
+
+        if aborted {
+            return Ok(None);
+        }
 
-            *handle_guard = Some(handle);
+        // 保存失败测试以及抽样的通过测试的遥测数据
+        if let (Some(capture), Some(ring)) = (test_param.capture.as_ref(), ring.as_ref()) {
+            let sampled =
+                capture.pass_sample_every > 0 && test_cnt % capture.pass_sample_every == 0;
+            if !record.passed || sampled {
+                let path = std::path::Path::new(&capture.dir).join(format!(
+                    "trial_{:05}_{}.csv",
+                    test_cnt,
+                    if record.passed { "pass" } else { "fail" }
+                ));
+                let path = path.to_string_lossy().to_string();
+                match ring.save_csv(&path) {
+                    std::result::Result::Ok(_) => {
+                        LOGGER.lock().unwrap().info(
+                            format!(
+                                "{}第{}次测试遥测数据已保存: {}帧, 实际采样间隔 {:.0}ms (配置 {}ms)",
+                                test_param.log_tag(),
+                                test_cnt,
+                                ring.sample_count(),
+                                ring.mean_interval_ms().unwrap_or_default(),
+                                capture.interval_ms
+                            )
+                            .as_str(),
+                        );
+                        record.telemetry_file = Some(path);
+                    }
+                    Err(err) => LOGGER
+                        .lock()
+                        .unwrap()
+                        .warning(format!("保存遥测数据失败: {}", err).as_str()),
+                }
+            }
         }
+
+        Ok(Some(record))
     }
 
     pub fn stop(&self) {
         LOGGER.lock().unwrap().warning(format!("停止测试").as_str());
 
-        let running = Arc::clone(&self.running);
         let mut handle_guard = self.handle.lock().unwrap();
-        running.store(false, Ordering::SeqCst);
+        self.request_stop();
 
         if let Some(handle) = handle_guard.take() {
             handle.join().unwrap();
         }
     }
 
-    fn get_test_status(rps: &f32) -> Result<TestStatus> {
+    /// 通知测试线程停止, 不等待线程退出
+    pub fn request_stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// 等待下一次判定, 配置了遥测采集时在等待期间高频采样
+    fn wait_and_capture(
+        motor: &MotorRef,
+        wait_ms: u32,
+        capture: Option<&TelemetryCaptureParam>,
+        ring: Option<&mut TelemetryRing>,
+        start: &Instant,
+    ) {
+        let until = Instant::now() + std::time::Duration::from_millis(wait_ms as u64);
+
+        match (capture, ring) {
+            (Some(capture), Some(ring)) => {
+                let interval = std::time::Duration::from_millis(capture.interval_ms as u64);
+                loop {
+                    let time_ms = start.elapsed().as_millis() as u64;
+                    if let std::result::Result::Ok(sample) =
+                        motor.lock().unwrap().get_motor_telemetry(time_ms)
+                    {
+                        ring.push(sample);
+                    }
+
+                    let now = Instant::now();
+                    if now >= until {
+                        break;
+                    }
+                    thread::sleep(interval.min(until - now));
+                }
+            }
+            _ => thread::sleep(until.saturating_duration_since(Instant::now())),
+        }
+    }
+
+    /// 启动超时的失败分类
+    fn classify_timeout(
+        states: &[String],
+        reached: bool,
+        max_rps: Option<f32>,
+        upper_rps: f32,
+    ) -> FailureCategory {
+        let last_state = states.last().map(|s| s.as_str()).unwrap_or_default();
+        if !states.iter().any(|s| s == "CL_RUNNING")
+            || matches!(last_state, "SEEK_POS" | "ALIGNMENT" | "OL_START")
+        {
+            return FailureCategory::StuckInState;
+        }
+
+        // 曾达到目标转速或超调超出容差, 说明在目标转速附近振荡
+        if reached || max_rps.is_some_and(|rps| rps > upper_rps) {
+            return FailureCategory::UnstableAtTarget;
+        }
+
+        FailureCategory::TimeoutBelowTarget
+    }
+
+    fn get_test_status(motor: &MotorRef, rps: &f32, tolerance: f32) -> Result<TrialSample> {
+        let mut sample = TrialSample {
+            status: TestStatus::Rotating,
+            rps: None,
+            error_code: None,
+            motor_state: None,
+        };
+
         // 1. 检测状态, 若报错则停止电机、返回错误
-        match MOTOR.lock().unwrap().get_motor_status() {
+        match motor.lock().unwrap().get_motor_status() {
             std::result::Result::Ok(status) => {
-                if status.error_code.unwrap() > 0 {
+                sample.error_code = status.error_code;
+                sample.motor_state = status.motor_state;
+                let error_code = status.error_code.unwrap_or_default();
+                if error_code > 0 {
                     LOGGER
                         .lock()
                         .unwrap()
-                        .warning(format!("故障状态: 0x{:x}", status.error_code.unwrap()).as_str());
-                    return Ok(TestStatus::RotatFailed);
+                        .warning(format!("故障状态: 0x{:x}", error_code).as_str());
+                    sample.status = TestStatus::RotatFailed;
+                    return Ok(sample);
                 }
             }
             Err(_) => {
-                return Ok(TestStatus::RotatFailed);
+                sample.status = TestStatus::RotatFailed;
+                return Ok(sample);
             }
         }
 
         // 2. 检测转速, 达到目标转速且状态正常则判断为启动成功
-        let curr_rps = MOTOR.lock().unwrap().get_current_rps().unwrap();
-        if libm::fabs((curr_rps - rps) as f64) <= 0.5 {
-            return Ok(TestStatus::RotatSuccess);
+        let curr_rps = match motor.lock().unwrap().get_current_rps() {
+            std::result::Result::Ok(rps) => rps,
+            Err(_) => {
+                sample.status = TestStatus::RotatFailed;
+                return Ok(sample);
+            }
+        };
+
+        // 通信失败时 get_current_rps 返回 -1000, get_motor_status 返回的 "IDEL" 状态也是占位值,
+        // 均不计入记录, 由调用方判定为通信失败
+        if curr_rps <= -1000.0 {
+            sample.status = TestStatus::RotatFailed;
+            sample.error_code = None;
+            sample.motor_state = None;
+            return Ok(sample);
+        }
+        sample.rps = Some(curr_rps);
+        if libm::fabs((curr_rps - rps) as f64) <= tolerance as f64 {
+            sample.status = TestStatus::RotatSuccess;
         }
 
-        Ok(TestStatus::Rotating)
+        Ok(sample)
     }
 
     pub fn get_test_result(&self) -> Result<TestResult> {
@@ -231,11 +989,271 @@ impl StartupTestHandle {
             t_cnt = 1;
         }
 
+        let mut counts = FailureCounts::default();
+        for record in self.records.lock().unwrap().iter() {
+            match record.category {
+                Some(FailureCategory::Fault) => counts.fault += 1,
+                Some(FailureCategory::StuckInState) => counts.stuck_in_state += 1,
+                Some(FailureCategory::TimeoutBelowTarget) => counts.timeout_below_target += 1,
+                Some(FailureCategory::UnstableAtTarget) => counts.unstable_at_target += 1,
+                Some(FailureCategory::CommError) => counts.comm_error += 1,
+                None => {}
+            }
+        }
+
         Ok(TestResult {
             // progress: Some(*test_cnt as f32 / *total_cnt as f32 + 0.001),
             progress: Some(((f_cnt + s_cnt) / t_cnt) as f32),
             failed_cnt: Some(f_cnt),
             success_cnt: Some(s_cnt),
+            failure_counts: Some(counts),
+            paused: Some(self.paused.load(Ordering::SeqCst)),
+            error: self.error.lock().unwrap().clone(),
         })
     }
+
+    pub fn get_total_count(&self) -> u32 {
+        *self.total_cnt.lock().unwrap()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn get_test_records(&self) -> Result<Vec<TrialRecord>> {
+        Ok(self.records.lock().unwrap().clone())
+    }
+
+    /// 导出测试报告, 根据扩展名选择格式:
+    /// json 包含测试参数、统计结果和每次测试记录; csv 只包含每次测试记录, 测试参数另存为同名 yaml;
+    /// html 为包含设备信息、统计结果、转速曲线图的独立报告
+    pub fn export_report(&self, path: &str) -> Result<()> {
+        let result = self.get_test_result()?;
+        let records = self.records.lock().unwrap();
+        let test_param = self.test_param.lock().unwrap();
+        let device = self.device.lock().unwrap().clone();
+
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match ext.as_str() {
+            "json" => {
+                let report = StartupTestReport {
+                    param: &test_param,
+                    result,
+                    trials: &records,
+                };
+                std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+            }
+            "csv" => {
+                let mut wtr = csv::Writer::from_path(path)?;
+                for record in records.iter() {
+                    wtr.serialize(record)?;
+                }
+                wtr.flush()?;
+
+                let yaml = serde_yaml::to_string(&*test_param)?;
+                let param_path = std::path::Path::new(path).with_extension("yaml");
+                crate::tools::save_yaml(yaml, param_path.to_str().unwrap())?;
+            }
+            "html" => {
+                std::fs::write(
+                    path,
+                    Self::render_html(device.as_ref(), &test_param, &result, &records)?,
+                )?;
+            }
+            _ => bail!("unsupported report format: {}", ext),
+        }
+
+        Ok(())
+    }
+
+    fn render_html(
+        device: Option<&DeviceSnapshot>,
+        test_param: &Option<StartUpTestParam>,
+        result: &TestResult,
+        records: &[TrialRecord],
+    ) -> Result<String> {
+        let mut body = html_device_section(device);
+
+        body.push_str("<h2>Configuration</h2>\n");
+        body.push_str(&html_kv_table(&serde_json::to_value(test_param)?));
+
+        // 统计结果
+        let passed = records.iter().filter(|r| r.passed).count();
+        let times: Vec<f64> = records
+            .iter()
+            .filter_map(|r| r.time_to_target_ms.map(|ms| ms as f64))
+            .collect();
+        let n = times.len().max(1) as f64;
+        let mean = times.iter().sum::<f64>() / n;
+        let std = libm::sqrt(times.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / n);
+
+        let summary = serde_json::json!({
+            "trials": records.len(),
+            "passed": passed,
+            "failed": records.len() - passed,
+            "pass_rate": format!("{:.2}%", passed as f64 * 100.0 / records.len().max(1) as f64),
+            "time_to_target_ms": {
+                "mean": if times.is_empty() { None } else { Some(format!("{:.1}", mean)) },
+                "std": if times.is_empty() { None } else { Some(format!("{:.1}", std)) },
+                "min": times.iter().copied().reduce(f64::min),
+                "max": times.iter().copied().reduce(f64::max),
+            },
+            "failure_counts": result.failure_counts,
+        });
+        body.push_str("<h2>Summary</h2>\n");
+        body.push_str(&html_kv_table(&summary));
+
+        let target = test_param.as_ref().map(|p| p.target_rps as f64);
+        body.push_str(&svg_line_chart(
+            "Time to target",
+            &records
+                .iter()
+                .filter_map(|r| r.time_to_target_ms.map(|ms| (r.index as f64, ms as f64)))
+                .collect::<Vec<_>>(),
+            None,
+            "trial",
+            "ms",
+        ));
+        body.push_str(&svg_line_chart(
+            "Final speed",
+            &records
+                .iter()
+                .filter_map(|r| r.final_rps.map(|rps| (r.index as f64, rps as f64)))
+                .collect::<Vec<_>>(),
+            target,
+            "trial",
+            "rps",
+        ));
+
+        // 每次测试记录
+        let rows: Vec<Vec<String>> = records
+            .iter()
+            .map(|r| {
+                vec![
+                    r.index.to_string(),
+                    html_escape(&r.start_time),
+                    r.time_to_target_ms
+                        .map_or(String::from("-"), |v| v.to_string()),
+                    r.final_rps
+                        .map_or(String::from("-"), |v| format!("{:.3}", v)),
+                    r.vdc_bus.map_or(String::from("-"), |v| format!("{:.2}", v)),
+                    r.supply_current_peak
+                        .map_or(String::from("-"), |v| format!("{:.3}", v)),
+                    r.error_code
+                        .map_or(String::from("-"), |v| format!("0x{:x}", v)),
+                    html_escape(&r.state_history),
+                    if r.passed {
+                        String::from("<span class=\"pass\">PASS</span>")
+                    } else {
+                        String::from("<span class=\"fail-text\">FAIL</span>")
+                    },
+                    html_escape(&r.reason),
+                ]
+            })
+            .collect();
+        let fail_rows: Vec<bool> = records.iter().map(|r| !r.passed).collect();
+        body.push_str("<h2>Trials</h2>\n");
+        body.push_str(&html_table(
+            &[
+                "#",
+                "start time",
+                "time to target (ms)",
+                "final rps",
+                "vdc (V)",
+                "peak current (A)",
+                "error",
+                "states",
+                "result",
+                "reason",
+            ],
+            &rows,
+            &fail_rows,
+        ));
+
+        // 保存了遥测数据的测试绘制转速曲线
+        let traces: Vec<&TrialRecord> = records
+            .iter()
+            .filter(|r| r.telemetry_file.is_some())
+            .collect();
+        if !traces.is_empty() {
+            body.push_str("<h2>Speed traces</h2>\n");
+            for r in traces {
+                let trace = load_telemetry_trace(r.telemetry_file.as_ref().unwrap());
+                body.push_str(&svg_line_chart(
+                    &format!(
+                        "Trial {} ({})",
+                        r.index,
+                        if r.passed { "pass" } else { "fail" }
+                    ),
+                    &trace,
+                    target,
+                    "time (s)",
+                    "rps",
+                ));
+            }
+        }
+
+        Ok(html_page("Startup Test Report", &body))
+    }
+}
+
+/// 读取测试进度文件
+pub fn load_startup_checkpoint(path: &str) -> Result<StartupTestCheckpoint> {
+    if !crate::tools::is_file_exist(path) {
+        bail!("File: {} not exist", path)
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let checkpoint: StartupTestCheckpoint = serde_json::from_str(&content)?;
+
+    Ok(checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states(states: &[&str]) -> Vec<String> {
+        states.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn classify_timeout_stuck_in_state() {
+        assert_eq!(
+            StartupTestHandle::classify_timeout(&states(&["IDLE", "ALIGNMENT"]), false, None, 55.0),
+            FailureCategory::StuckInState
+        );
+        assert_eq!(
+            StartupTestHandle::classify_timeout(
+                &states(&["CL_RUNNING", "OL_START"]),
+                true,
+                Some(60.0),
+                55.0
+            ),
+            FailureCategory::StuckInState
+        );
+    }
+
+    #[test]
+    fn classify_timeout_running() {
+        let running = states(&["OL_START", "CL_RUNNING"]);
+
+        assert_eq!(
+            StartupTestHandle::classify_timeout(&running, true, Some(50.0), 55.0),
+            FailureCategory::UnstableAtTarget
+        );
+        assert_eq!(
+            StartupTestHandle::classify_timeout(&running, false, Some(60.0), 55.0),
+            FailureCategory::UnstableAtTarget
+        );
+        assert_eq!(
+            StartupTestHandle::classify_timeout(&running, false, Some(30.0), 55.0),
+            FailureCategory::TimeoutBelowTarget
+        );
+    }
 }
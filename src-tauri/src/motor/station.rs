@@ -0,0 +1,392 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use crate::tools::switch_power;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+fn default_power_on_delay_ms() -> u32 {
+    2000
+}
+
+/// 产线工位执行的测试: yaml 测试计划或启停测试
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StationTest {
+    Plan { path: String },
+    StartupTest { param: StartUpTestParam },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StationRunParam {
+    pub serial_number: String,
+    pub operator: String,
+    pub test: StationTest,
+    pub history_dir: String, // 每个序列号的历史记录保存在 history_dir/<serial_number>.jsonl
+    #[serde(default)]
+    pub has_relay: bool,
+    #[serde(default = "default_power_on_delay_ms")]
+    pub power_on_delay_ms: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StationMetric {
+    pub name: String,
+    pub value: Option<f64>,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// 一次产线测试的结果记录
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StationRecord {
+    pub serial_number: String,
+    pub operator: String,
+    pub test_name: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub passed: bool,
+    pub message: String,
+    pub firmware_version: String,
+    pub motor_params: Option<MotorStaticParams>,
+    pub metrics: Vec<StationMetric>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct StationStatus {
+    pub running: bool,
+    pub stage: String,
+    pub serial_number: String,
+    pub passed: Option<bool>,
+    pub record: Option<StationRecord>,
+}
+
+pub struct StationHandle {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    status: Arc<Mutex<StationStatus>>,
+}
+
+/// 序列号中不能用于文件名的字符替换为 _
+fn history_path(dir: &str, serial_number: &str) -> std::path::PathBuf {
+    let name: String = serial_number
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    std::path::Path::new(dir).join(format!("{}.jsonl", name))
+}
+
+/// 读取某个序列号的全部历史测试记录, 按测试时间先后排列
+pub fn load_station_history(dir: &str, serial_number: &str) -> Result<Vec<StationRecord>> {
+    let path = history_path(dir, serial_number);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(path)?;
+    let mut records = Vec::new();
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        records.push(serde_json::from_str(line)?);
+    }
+
+    Ok(records)
+}
+
+fn append_station_history(dir: &str, record: &StationRecord) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path(dir, &record.serial_number))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+
+    Ok(())
+}
+
+impl StationHandle {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            status: Arc::new(Mutex::new(StationStatus {
+                stage: String::from("idle"),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// 产线测试流程: 上电 -> 读取固件版本和电机参数 -> 执行测试 -> 断电 -> 保存记录
+    pub fn start(&self, device: Device, param: StationRunParam) -> Result<()> {
+        if param.serial_number.trim().is_empty() {
+            bail!("serial number is empty");
+        }
+
+        if param.history_dir.is_empty() {
+            bail!("history dir is invalid");
+        }
+
+        if device.motor.lock().unwrap().port.is_none() {
+            bail!("motor port is not connected");
+        }
+
+        // 提前加载测试计划, 文件错误时直接返回
+        let plan = match param.test {
+            StationTest::Plan { ref path } => Some(load_test_plan(path)?),
+            StationTest::StartupTest { .. } => None,
+        };
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("station test is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        *self.status.lock().unwrap() = StationStatus {
+            running: true,
+            stage: String::from("power_on"),
+            serial_number: param.serial_number.trim().to_string(),
+            ..Default::default()
+        };
+
+        let running = Arc::clone(&self.running);
+        let status = Arc::clone(&self.status);
+        running.store(true, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            let serial_number = param.serial_number.trim().to_string();
+            LOGGER
+                .lock()
+                .unwrap()
+                .info(format!("开始产线测试, 序列号: {}", serial_number).as_str());
+
+            let mut record = StationRecord {
+                serial_number: serial_number.clone(),
+                operator: param.operator.clone(),
+                test_name: match plan {
+                    Some(ref plan) => plan.name.clone(),
+                    None => String::from("startup_test"),
+                },
+                start_time: chrono::Local::now().to_rfc3339(),
+                end_time: String::new(),
+                passed: false,
+                message: String::new(),
+                firmware_version: String::new(),
+                motor_params: None,
+                metrics: Vec::new(),
+            };
+
+            match Self::run(&device, &param, plan, &running, &status, &mut record) {
+                core::result::Result::Ok(passed) => record.passed = passed,
+                Err(err) => {
+                    record.passed = false;
+                    record.message = err.to_string();
+                }
+            }
+
+            if !running.load(Ordering::SeqCst) && record.message.is_empty() {
+                record.passed = false;
+                record.message = String::from("aborted");
+            }
+
+            // 电机断电
+            if param.has_relay {
+                switch_power(|relay| relay.turn_off()).ok();
+            }
+
+            record.end_time = chrono::Local::now().to_rfc3339();
+            if let Err(err) = append_station_history(&param.history_dir, &record) {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .danger(format!("保存测试记录失败: {}", err).as_str());
+            }
+
+            if record.passed {
+                LOGGER
+                    .lock()
+                    .unwrap()
+                    .info(format!("序列号 {} 测试通过", serial_number).as_str());
+            } else {
+                LOGGER.lock().unwrap().danger(
+                    format!("序列号 {} 测试未通过 {}", serial_number, record.message).as_str(),
+                );
+            }
+
+            let snapshot = {
+                let mut status = status.lock().unwrap();
+                status.running = false;
+                status.stage = String::from("finished");
+                status.passed = Some(record.passed);
+                status.record = Some(record);
+                status.clone()
+            };
+            LOGGER.lock().unwrap().emit("station_result", snapshot);
+
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    fn run(
+        device: &Device,
+        param: &StationRunParam,
+        plan: Option<TestPlan>,
+        running: &AtomicBool,
+        status: &Mutex<StationStatus>,
+        record: &mut StationRecord,
+    ) -> Result<bool> {
+        if param.has_relay {
+            switch_power(|relay| relay.turn_on())?;
+        }
+        thread::sleep(Duration::from_millis(param.power_on_delay_ms as u64));
+
+        status.lock().unwrap().stage = String::from("read_params");
+        let params = device.motor.lock().unwrap().get_motor_static_params()?;
+        record.firmware_version = format!(
+            "{}.{}.{}{}",
+            params.main_version.unwrap_or_default(),
+            params.sub_version.unwrap_or_default(),
+            params.rev_version.unwrap_or_default(),
+            params.stage_version.unwrap_or('a')
+        );
+        record.motor_params = Some(params);
+
+        status.lock().unwrap().stage = String::from("testing");
+        match plan {
+            Some(plan) => Self::run_plan(device, plan, running, record),
+            None => match param.test {
+                StationTest::StartupTest { ref param } => {
+                    Self::run_startup_test(device, param.clone(), running, record)
+                }
+                StationTest::Plan { .. } => bail!("test plan is not loaded"),
+            },
+        }
+    }
+
+    fn run_plan(
+        device: &Device,
+        plan: TestPlan,
+        running: &AtomicBool,
+        record: &mut StationRecord,
+    ) -> Result<bool> {
+        let test_plan = &device.tasks.test_plan;
+        test_plan.lock().unwrap().start(device.clone(), plan)?;
+
+        loop {
+            thread::sleep(Duration::from_millis(200));
+            if !running.load(Ordering::SeqCst) {
+                test_plan.lock().unwrap().stop();
+            }
+            if !test_plan.lock().unwrap().get_result()?.running {
+                break;
+            }
+        }
+
+        let result = test_plan.lock().unwrap().get_result()?;
+        for step in result.steps.iter() {
+            record.metrics.push(StationMetric {
+                name: format!("{} {}", step.index, step.name),
+                value: step.value,
+                passed: step.passed,
+                message: step.message.clone(),
+            });
+        }
+
+        Ok(result.passed.unwrap_or(false))
+    }
+
+    fn run_startup_test(
+        device: &Device,
+        param: StartUpTestParam,
+        running: &AtomicBool,
+        record: &mut StationRecord,
+    ) -> Result<bool> {
+        let startup_test = &device.tasks.startup_test;
+        startup_test
+            .lock()
+            .unwrap()
+            .start(device.motor.clone(), param)?;
+
+        loop {
+            thread::sleep(Duration::from_millis(200));
+            if !running.load(Ordering::SeqCst) {
+                startup_test.lock().unwrap().stop();
+            }
+            if !startup_test.lock().unwrap().is_running() {
+                break;
+            }
+        }
+
+        if let Some(err) = startup_test.lock().unwrap().get_test_result()?.error {
+            bail!(err);
+        }
+
+        let trials = startup_test.lock().unwrap().get_test_records()?;
+        let success_cnt = trials.iter().filter(|t| t.passed).count();
+        let failed_cnt = trials.len() - success_cnt;
+        let times: Vec<f64> = trials
+            .iter()
+            .filter_map(|t| t.time_to_target_ms.map(|ms| ms as f64))
+            .collect();
+
+        record.metrics.push(StationMetric {
+            name: String::from("success_cnt"),
+            value: Some(success_cnt as f64),
+            passed: true,
+            message: String::new(),
+        });
+        record.metrics.push(StationMetric {
+            name: String::from("failed_cnt"),
+            value: Some(failed_cnt as f64),
+            passed: failed_cnt == 0,
+            message: trials
+                .iter()
+                .filter(|t| !t.passed)
+                .map(|t| format!("#{} {}", t.index, t.reason))
+                .collect::<Vec<String>>()
+                .join("; "),
+        });
+        if !times.is_empty() {
+            record.metrics.push(StationMetric {
+                name: String::from("mean_time_to_target_ms"),
+                value: Some(times.iter().sum::<f64>() / times.len() as f64),
+                passed: true,
+                message: String::new(),
+            });
+        }
+
+        Ok(!trials.is_empty() && failed_cnt == 0)
+    }
+
+    pub fn stop(&self) {
+        LOGGER.lock().unwrap().warning("停止产线测试");
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_status(&self) -> Result<StationStatus> {
+        Ok(self.status.lock().unwrap().clone())
+    }
+}
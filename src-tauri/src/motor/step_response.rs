@@ -0,0 +1,307 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn default_sample_interval_ms() -> u32 {
+    10
+}
+
+fn default_settle_ms() -> u32 {
+    3000
+}
+
+fn default_settling_band() -> f32 {
+    2.0
+}
+
+fn default_stop_after() -> bool {
+    true
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StepTestParam {
+    pub init_rps: f32,
+    pub target_rps: f32,
+    pub duration_ms: u32,
+    #[serde(default = "default_sample_interval_ms")]
+    pub sample_interval_ms: u32,
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u32, // 阶跃前在初始转速下的稳定时间
+    #[serde(default = "default_settling_band")]
+    pub settling_band: f32, // 调节时间的误差带, 单位: 阶跃幅值的百分比
+    #[serde(default = "default_stop_after")]
+    pub stop_after: bool,
+    #[serde(default)]
+    pub save_path: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StepSample {
+    pub time_ms: f32,
+    pub setpoint: f32,
+    pub rps: f32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StepMetrics {
+    pub rise_time_ms: Option<f32>,
+    pub overshoot_pct: Option<f32>,
+    pub settling_time_ms: Option<f32>,
+    pub steady_state_error: Option<f32>,
+    pub peak_rps: Option<f32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct StepTestResult {
+    pub kp_spd: Option<f64>,
+    pub ki_spd: Option<f64>,
+    pub metrics: StepMetrics,
+    pub trace: Vec<StepSample>,
+}
+
+#[derive(Debug, Serialize)]
+struct StepTestSummary<'a> {
+    param: &'a StepTestParam,
+    kp_spd: Option<f64>,
+    ki_spd: Option<f64>,
+    metrics: &'a StepMetrics,
+}
+
+/// 运行到初始转速, 下发阶跃并按采样间隔记录转速
+fn drive_step(motor: &MotorRef, param: &StepTestParam) -> Result<Vec<StepSample>> {
+    // 运行到初始转速
+    motor
+        .lock()
+        .unwrap()
+        .update_motor_speed_rps((param.init_rps * 100000.0) as u32)?;
+    thread::sleep(Duration::from_millis(100));
+    if param.init_rps > 0.0 {
+        let state = motor.lock().unwrap().get_motor_status()?.motor_state;
+        if state != Some(String::from("CL_RUNNING")) {
+            motor.lock().unwrap().start_motor()?;
+        }
+    }
+    thread::sleep(Duration::from_millis(param.settle_ms as u64));
+
+    // 下发阶跃并采样
+    let mut trace = Vec::new();
+    let start = Instant::now();
+    motor
+        .lock()
+        .unwrap()
+        .update_motor_speed_rps((param.target_rps * 100000.0) as u32)?;
+    if param.init_rps <= 0.0 {
+        motor.lock().unwrap().start_motor()?;
+    }
+
+    let interval = Duration::from_millis(param.sample_interval_ms as u64);
+    let mut next_sample = start;
+    while start.elapsed().as_millis() < param.duration_ms as u128 {
+        let rps = motor.lock().unwrap().get_current_rps()?;
+        // 通信失败时 get_current_rps 返回 -1000
+        if rps > -1000.0 {
+            trace.push(StepSample {
+                time_ms: start.elapsed().as_secs_f32() * 1000.0,
+                setpoint: param.target_rps,
+                rps,
+            });
+        }
+
+        next_sample += interval;
+        let now = Instant::now();
+        if next_sample > now {
+            thread::sleep(next_sample - now);
+        }
+    }
+
+    Ok(trace)
+}
+
+/// 转速阶跃响应测试: 先在初始转速下稳定, 然后通过 update_motor_speed_rps 下发目标转速, 高频采样转速并计算响应指标
+pub fn run_step_test(motor: &MotorRef, param: &StepTestParam) -> Result<StepTestResult> {
+    if param.duration_ms == 0 || param.sample_interval_ms == 0 {
+        bail!("duration and sample interval must be greater than 0");
+    }
+
+    if motor.lock().unwrap().port.is_none() {
+        bail!("motor port is not connected");
+    }
+
+    let static_params = motor.lock().unwrap().get_motor_static_params()?;
+
+    LOGGER.lock().unwrap().info(
+        format!(
+            "阶跃测试: {} rps -> {} rps, kp_spd: {}, ki_spd: {}",
+            param.init_rps,
+            param.target_rps,
+            static_params.kp_spd.unwrap_or_default(),
+            static_params.ki_spd.unwrap_or_default()
+        )
+        .as_str(),
+    );
+
+    // 测试过程中出错时电机可能已经启动, 停机后再返回错误
+    let trace = match drive_step(motor, param) {
+        core::result::Result::Ok(trace) => trace,
+        Err(err) => {
+            let mut motor = motor.lock().unwrap();
+            motor.update_motor_speed_hz(0).ok();
+            motor.stop_motor().ok();
+            return Err(err);
+        }
+    };
+
+    if param.stop_after {
+        motor.lock().unwrap().update_motor_speed_hz(0)?;
+        motor.lock().unwrap().stop_motor()?;
+    }
+
+    let metrics = analyze_step(
+        &trace,
+        param.init_rps,
+        param.target_rps,
+        param.settling_band,
+    );
+
+    let result = StepTestResult {
+        kp_spd: static_params.kp_spd,
+        ki_spd: static_params.ki_spd,
+        metrics,
+        trace,
+    };
+
+    if let Some(ref path) = param.save_path {
+        if !path.is_empty() {
+            save_step_result(param, &result, path)?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// 计算阶跃响应指标: 上升时间(10%~90%)、超调量、调节时间和稳态误差
+pub fn analyze_step(trace: &[StepSample], init: f32, target: f32, band_pct: f32) -> StepMetrics {
+    let delta = target - init;
+    if trace.is_empty() || libm::fabsf(delta) < f32::EPSILON {
+        return StepMetrics {
+            rise_time_ms: None,
+            overshoot_pct: None,
+            settling_time_ms: None,
+            steady_state_error: None,
+            peak_rps: None,
+        };
+    }
+
+    // 归一化到 0 ~ 1, 兼容减速阶跃
+    let norm = |rps: f32| (rps - init) / delta;
+
+    let t10 = trace.iter().find(|s| norm(s.rps) >= 0.1).map(|s| s.time_ms);
+    let t90 = trace.iter().find(|s| norm(s.rps) >= 0.9).map(|s| s.time_ms);
+    let rise_time_ms = match (t10, t90) {
+        (Some(t10), Some(t90)) => Some(t90 - t10),
+        _ => None,
+    };
+
+    let peak = trace
+        .iter()
+        .max_by(|a, b| norm(a.rps).total_cmp(&norm(b.rps)))
+        .unwrap();
+    let overshoot_pct = ((norm(peak.rps) - 1.0) * 100.0).max(0.0);
+
+    let band = libm::fabsf(delta) * band_pct / 100.0;
+    let settling_time_ms = match trace
+        .iter()
+        .rposition(|s| libm::fabsf(s.rps - target) > band)
+    {
+        Some(idx) if idx + 1 < trace.len() => Some(trace[idx + 1].time_ms),
+        Some(_) => None,
+        None => Some(trace[0].time_ms),
+    };
+
+    // 取最后 20% 的采样均值作为稳态值
+    let tail = &trace[trace.len() - (trace.len() / 5).max(1)..];
+    let steady = tail.iter().map(|s| s.rps).sum::<f32>() / tail.len() as f32;
+
+    StepMetrics {
+        rise_time_ms,
+        overshoot_pct: Some(overshoot_pct),
+        settling_time_ms,
+        steady_state_error: Some(target - steady),
+        peak_rps: Some(peak.rps),
+    }
+}
+
+/// 保存原始转速曲线(csv)以及测试参数、PI参数和指标(yaml)
+fn save_step_result(param: &StepTestParam, result: &StepTestResult, path: &str) -> Result<()> {
+    let mut wtr = csv::Writer::from_path(path)?;
+    for sample in &result.trace {
+        wtr.serialize(sample)?;
+    }
+    wtr.flush()?;
+
+    let summary = StepTestSummary {
+        param,
+        kp_spd: result.kp_spd,
+        ki_spd: result.ki_spd,
+        metrics: &result.metrics,
+    };
+    let yaml = serde_yaml::to_string(&summary)?;
+    let summary_path = std::path::Path::new(path).with_extension("yaml");
+    crate::tools::save_yaml(yaml, summary_path.to_str().unwrap())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(rps: &[f32]) -> Vec<StepSample> {
+        rps.iter()
+            .enumerate()
+            .map(|(i, rps)| StepSample {
+                time_ms: i as f32 * 10.0,
+                setpoint: 0.0,
+                rps: *rps,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn analyze_step_up() {
+        let trace = trace(&[10.0, 12.0, 16.0, 19.5, 21.0, 20.1, 19.9, 20.0, 20.0, 20.0]);
+        let metrics = analyze_step(&trace, 10.0, 20.0, 2.0);
+
+        assert_eq!(metrics.rise_time_ms, Some(20.0));
+        assert_eq!(metrics.peak_rps, Some(21.0));
+        assert!((metrics.overshoot_pct.unwrap() - 10.0).abs() < 1e-3);
+        assert_eq!(metrics.settling_time_ms, Some(50.0));
+        assert!(metrics.steady_state_error.unwrap().abs() < 1e-3);
+    }
+
+    #[test]
+    fn analyze_step_down() {
+        let trace = trace(&[20.0, 17.0, 12.0, 10.0, 10.0]);
+        let metrics = analyze_step(&trace, 20.0, 10.0, 2.0);
+
+        assert_eq!(metrics.rise_time_ms, Some(20.0));
+        assert_eq!(metrics.overshoot_pct, Some(0.0));
+        assert_eq!(metrics.settling_time_ms, Some(30.0));
+    }
+
+    #[test]
+    fn analyze_step_unsettled_or_empty() {
+        let metrics = analyze_step(&trace(&[10.0, 12.0, 14.0]), 10.0, 20.0, 2.0);
+        assert_eq!(metrics.settling_time_ms, None);
+        assert_eq!(metrics.rise_time_ms, None);
+
+        assert_eq!(analyze_step(&[], 10.0, 20.0, 2.0).overshoot_pct, None);
+        assert_eq!(
+            analyze_step(&trace(&[10.0]), 10.0, 10.0, 2.0).peak_rps,
+            None
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::{Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TelemetrySample {
+    pub time_ms: u64,
+    pub rps: f32,
+    pub error_code: u16,
+    pub motor_state: String,
+    pub vdc_bus: f64,
+    pub ia: f32,
+    pub ib: f32,
+    pub ic: f32,
+}
+
+/// 固定容量的遥测环形缓冲, 写满后丢弃最早的数据
+pub struct TelemetryRing {
+    capacity: usize,
+    samples: VecDeque<TelemetrySample>,
+}
+
+impl TelemetryRing {
+    pub fn new(capacity: usize) -> Self {
+        TelemetryRing {
+            capacity: capacity.max(1),
+            samples: VecDeque::with_capacity(capacity.max(1)),
+        }
+    }
+
+    pub fn push(&mut self, sample: TelemetrySample) {
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// 实际的平均采样间隔, 一帧需要多次请求, 可能达不到配置的采样间隔
+    pub fn mean_interval_ms(&self) -> Option<f64> {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) if self.samples.len() > 1 => {
+                Some((last.time_ms - first.time_ms) as f64 / (self.samples.len() - 1) as f64)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn save_csv(&self, path: &str) -> Result<()> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        for sample in self.samples.iter() {
+            wtr.serialize(sample)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
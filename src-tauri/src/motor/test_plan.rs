@@ -0,0 +1,727 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use crate::tools;
+use crate::tools::{switch_power, POWER_SUPPLY};
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn default_sample_interval_ms() -> u32 {
+    100
+}
+
+fn default_tolerance() -> f32 {
+    0.5
+}
+
+/// 断言使用的遥测量
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanMetric {
+    Rps,           // 窗口内平均转速
+    Jitter,        // 窗口内转速标准差
+    VdcBus,        // 窗口内平均母线电压
+    RsOnline,      // 在线辨识电阻
+    ErrorCode,     // 窗口内出现的故障码(按位或)
+    SupplyVoltage, // 窗口内程控电源平均输出电压
+    SupplyCurrent, // 窗口内程控电源平均输出电流
+}
+
+/// 测试计划中的一个步骤, 以 action 字段区分类型
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlanStep {
+    RelayOn,
+    RelayOff,
+    /// 程控电源输出开关
+    SupplyOn,
+    SupplyOff,
+    /// 设置程控电源输出电压和限流, 可用于降压运行
+    SetSupply {
+        voltage: f64,
+        #[serde(default)]
+        current_limit: Option<f64>,
+    },
+    Wait {
+        ms: u64,
+    },
+    /// 固件版本检查, 版本格式为 main.sub.rev
+    CheckVersion {
+        #[serde(default)]
+        equals: Option<String>,
+        #[serde(default)]
+        min: Option<String>,
+    },
+    /// 从 yaml 文件写入电机参数(与导出的参数文件格式相同)
+    ApplyParams {
+        path: String,
+    },
+    ClearFaults,
+    RsOnline {
+        enable: bool,
+    },
+    SetRps {
+        rps: f32,
+        #[serde(default)]
+        ramp: Option<SCurveParam>,
+    },
+    StartMotor {
+        rps: f32,
+    },
+    StopMotor,
+    /// 等待转速连续 stable_ms 处于 rps ± tolerance 之内
+    WaitStable {
+        rps: f32,
+        #[serde(default = "default_tolerance")]
+        tolerance: f32,
+        stable_ms: u64,
+        timeout_ms: u64,
+        #[serde(default = "default_sample_interval_ms")]
+        sample_interval_ms: u32,
+    },
+    /// 在 window_ms 内采样遥测量, 检查是否处于 [min, max] 之内
+    Assert {
+        metric: PlanMetric,
+        #[serde(default)]
+        window_ms: u64,
+        #[serde(default = "default_sample_interval_ms")]
+        sample_interval_ms: u32,
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// 执行转速曲线文件, 曲线执行完成后继续
+    RunProfile {
+        path: String,
+        #[serde(default)]
+        scurve: Option<SCurveParam>,
+    },
+    Loop {
+        count: u32,
+        steps: Vec<PlanStepDef>,
+    },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct PlanStepDef {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub continue_on_fail: bool,
+    #[serde(flatten)]
+    pub step: PlanStep,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct TestPlan {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub steps: Vec<PlanStepDef>,
+    #[serde(default)]
+    pub cleanup: Vec<PlanStepDef>, // 无论成功与否最后执行, 一般用于停机、断电
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct PlanStepResult {
+    pub index: String,
+    pub name: String,
+    pub action: String,
+    pub passed: bool,
+    pub value: Option<f64>,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct PlanResult {
+    pub running: bool,
+    pub plan_name: String,
+    pub current_step: String,
+    pub passed: Option<bool>,
+    pub start_time: String,
+    pub steps: Vec<PlanStepResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct PlanReport<'a> {
+    plan: &'a Option<TestPlan>,
+    result: &'a PlanResult,
+}
+
+pub struct TestPlanRunner {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    result: Arc<Mutex<PlanResult>>,
+    plan: Arc<Mutex<Option<TestPlan>>>,
+}
+
+/// 读取 yaml 测试计划文件
+pub fn load_test_plan(path: &str) -> Result<TestPlan> {
+    if !tools::is_file_exist(path) {
+        bail!("File: {} not exist", path)
+    }
+
+    let plan: TestPlan = match tools::read_yaml(path) {
+        core::result::Result::Ok(plan) => plan,
+        Err(err) => bail!("Failed to read test plan: {}", err),
+    };
+
+    if plan.steps.is_empty() {
+        bail!("test plan has no steps");
+    }
+
+    Ok(plan)
+}
+
+fn parse_version(version: &str) -> Result<(u8, u8, u8)> {
+    let parts: Vec<&str> = version.trim().split('.').collect();
+    if parts.len() != 3 {
+        bail!("invalid version: {}", version);
+    }
+
+    Ok((parts[0].parse()?, parts[1].parse()?, parts[2].parse()?))
+}
+
+impl PlanStep {
+    fn action(&self) -> &'static str {
+        match self {
+            PlanStep::RelayOn => "relay_on",
+            PlanStep::RelayOff => "relay_off",
+            PlanStep::SupplyOn => "supply_on",
+            PlanStep::SupplyOff => "supply_off",
+            PlanStep::SetSupply { .. } => "set_supply",
+            PlanStep::Wait { .. } => "wait",
+            PlanStep::CheckVersion { .. } => "check_version",
+            PlanStep::ApplyParams { .. } => "apply_params",
+            PlanStep::ClearFaults => "clear_faults",
+            PlanStep::RsOnline { .. } => "rs_online",
+            PlanStep::SetRps { .. } => "set_rps",
+            PlanStep::StartMotor { .. } => "start_motor",
+            PlanStep::StopMotor => "stop_motor",
+            PlanStep::WaitStable { .. } => "wait_stable",
+            PlanStep::Assert { .. } => "assert",
+            PlanStep::RunProfile { .. } => "run_profile",
+            PlanStep::Loop { .. } => "loop",
+        }
+    }
+}
+
+/// 单个步骤的执行结果: (是否通过, 测量值, 说明)
+type StepOutcome = (bool, Option<f64>, String);
+
+impl TestPlanRunner {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            result: Arc::new(Mutex::new(PlanResult::default())),
+            plan: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn start(&self, device: Device, plan: TestPlan) -> Result<()> {
+        if device.motor.lock().unwrap().port.is_none() {
+            bail!("motor port is not connected");
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("test plan is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        *self.plan.lock().unwrap() = Some(plan.clone());
+        *self.result.lock().unwrap() = PlanResult {
+            running: true,
+            plan_name: plan.name.clone(),
+            start_time: chrono::Local::now().to_rfc3339(),
+            ..Default::default()
+        };
+
+        let running = Arc::clone(&self.running);
+        let result = Arc::clone(&self.result);
+        running.store(true, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            LOGGER
+                .lock()
+                .unwrap()
+                .info(format!("开始执行测试计划: {}", plan.name).as_str());
+
+            let passed = Self::run_steps(&device, &plan.steps, "", "", &running, &result);
+            let aborted = !running.load(Ordering::SeqCst);
+
+            // 清理步骤在测试被中止时也需要执行
+            let cleanup_running = AtomicBool::new(true);
+            Self::run_steps(
+                &device,
+                &plan.cleanup,
+                "cleanup.",
+                "",
+                &cleanup_running,
+                &result,
+            );
+
+            {
+                let mut result = result.lock().unwrap();
+                result.passed = Some(passed && !aborted);
+                result.running = false;
+                result.current_step = String::new();
+            }
+
+            if aborted {
+                LOGGER.lock().unwrap().warning("测试计划已中止");
+            } else if passed {
+                LOGGER.lock().unwrap().info("测试计划通过");
+            } else {
+                LOGGER.lock().unwrap().danger("测试计划未通过");
+            }
+
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    /// 按顺序执行步骤, 返回是否全部通过
+    ///
+    /// 步骤序号为 prefix + 序号 + suffix, 循环内的子步骤以 "3.2#1" 表示第3步第2个子步骤的第1轮
+    fn run_steps(
+        device: &Device,
+        steps: &[PlanStepDef],
+        prefix: &str,
+        suffix: &str,
+        running: &AtomicBool,
+        result: &Mutex<PlanResult>,
+    ) -> bool {
+        let mut all_passed = true;
+
+        for (i, def) in steps.iter().enumerate() {
+            if !running.load(Ordering::SeqCst) {
+                return false;
+            }
+
+            let index = format!("{}{}{}", prefix, i + 1, suffix);
+            let passed = match def.step {
+                PlanStep::Loop {
+                    count,
+                    steps: ref sub_steps,
+                } => {
+                    let mut passed = true;
+                    for n in 1..=count {
+                        let loop_passed = Self::run_steps(
+                            device,
+                            sub_steps,
+                            &format!("{}.", index),
+                            &format!("#{}", n),
+                            running,
+                            result,
+                        );
+                        passed &= loop_passed;
+                        if !loop_passed && !def.continue_on_fail {
+                            break;
+                        }
+                    }
+                    passed
+                }
+                _ => Self::run_one(device, def, &index, running, result),
+            };
+
+            if !passed {
+                all_passed = false;
+                if !def.continue_on_fail {
+                    return false;
+                }
+            }
+        }
+
+        all_passed
+    }
+
+    fn run_one(
+        device: &Device,
+        def: &PlanStepDef,
+        index: &str,
+        running: &AtomicBool,
+        result: &Mutex<PlanResult>,
+    ) -> bool {
+        let action = def.step.action();
+        let name = def.name.clone().unwrap_or(String::from(action));
+        result.lock().unwrap().current_step = index.to_string();
+
+        let start = Instant::now();
+        let (passed, value, message) = match Self::exec_step(device, &def.step, running) {
+            core::result::Result::Ok(outcome) => outcome,
+            Err(err) => (false, None, err.to_string()),
+        };
+
+        let step_result = PlanStepResult {
+            index: index.to_string(),
+            name: name.clone(),
+            action: String::from(action),
+            passed,
+            value,
+            message: message.clone(),
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+
+        if passed {
+            LOGGER
+                .lock()
+                .unwrap()
+                .info(format!("步骤 {} {}: 通过 {}", index, name, message).as_str());
+        } else {
+            LOGGER
+                .lock()
+                .unwrap()
+                .danger(format!("步骤 {} {}: 失败 {}", index, name, message).as_str());
+        }
+
+        let snapshot = {
+            let mut result = result.lock().unwrap();
+            result.steps.push(step_result);
+            result.clone()
+        };
+        LOGGER.lock().unwrap().emit("test_plan_progress", snapshot);
+
+        passed
+    }
+
+    fn exec_step(device: &Device, step: &PlanStep, running: &AtomicBool) -> Result<StepOutcome> {
+        let motor = &device.motor;
+        match step {
+            PlanStep::RelayOn => {
+                switch_power(|relay| relay.turn_on())?;
+            }
+            PlanStep::RelayOff => {
+                switch_power(|relay| relay.turn_off())?;
+            }
+            PlanStep::SupplyOn => {
+                POWER_SUPPLY.lock().unwrap().set_output(true)?;
+            }
+            PlanStep::SupplyOff => {
+                POWER_SUPPLY.lock().unwrap().set_output(false)?;
+            }
+            PlanStep::SetSupply {
+                voltage,
+                current_limit,
+            } => {
+                let mut supply = POWER_SUPPLY.lock().unwrap();
+                if let Some(current) = current_limit {
+                    supply.set_current_limit(*current)?;
+                }
+                supply.set_voltage(*voltage)?;
+            }
+            PlanStep::Wait { ms } => {
+                Self::sleep(*ms, running);
+            }
+            PlanStep::CheckVersion { equals, min } => {
+                let params = motor.lock().unwrap().get_motor_static_params()?;
+                let version = (
+                    params.main_version.unwrap_or_default(),
+                    params.sub_version.unwrap_or_default(),
+                    params.rev_version.unwrap_or_default(),
+                );
+                let text = format!("{}.{}.{}", version.0, version.1, version.2);
+
+                if let Some(equals) = equals {
+                    if parse_version(equals)? != version {
+                        return Ok((false, None, format!("version {} != {}", text, equals)));
+                    }
+                }
+                if let Some(min) = min {
+                    if version < parse_version(min)? {
+                        return Ok((false, None, format!("version {} < {}", text, min)));
+                    }
+                }
+                return Ok((true, None, format!("version {}", text)));
+            }
+            PlanStep::ApplyParams { path } => {
+                if !tools::is_file_exist(path) {
+                    bail!("File: {} not exist", path)
+                }
+                let params: MotorSpecialParams = tools::read_yaml(path)?;
+                motor.lock().unwrap().update_motor_special_params(params)?;
+            }
+            PlanStep::ClearFaults => {
+                motor.lock().unwrap().clear_motor_faults()?;
+            }
+            PlanStep::RsOnline { enable } => {
+                motor.lock().unwrap().set_motor_rs_online_enable(*enable)?;
+            }
+            PlanStep::SetRps { rps, ramp } => match ramp {
+                Some(ramp) => {
+                    // 通信失败时 get_current_rps 返回 -1000, 与 update_motor_rps 一样从 0 开始
+                    let from = motor.lock().unwrap().get_current_rps()?.max(0.0);
+                    stream_scurve(motor, from, *rps, ramp, running)?;
+                }
+                None => {
+                    motor
+                        .lock()
+                        .unwrap()
+                        .update_motor_speed_rps((rps * 100000.0) as u32)?;
+                }
+            },
+            PlanStep::StartMotor { rps } => {
+                motor
+                    .lock()
+                    .unwrap()
+                    .update_motor_speed_rps((rps * 100000.0) as u32)?;
+                thread::sleep(Duration::from_millis(100));
+                motor.lock().unwrap().start_motor()?;
+            }
+            PlanStep::StopMotor => {
+                motor.lock().unwrap().stop_motor()?;
+            }
+            PlanStep::WaitStable {
+                rps,
+                tolerance,
+                stable_ms,
+                timeout_ms,
+                sample_interval_ms,
+            } => {
+                return Self::wait_stable(
+                    motor,
+                    *rps,
+                    *tolerance,
+                    *stable_ms,
+                    *timeout_ms,
+                    *sample_interval_ms,
+                    running,
+                );
+            }
+            PlanStep::Assert {
+                metric,
+                window_ms,
+                sample_interval_ms,
+                min,
+                max,
+            } => {
+                let value =
+                    Self::measure(motor, *metric, *window_ms, *sample_interval_ms, running)?;
+                let passed =
+                    min.map_or(true, |min| value >= min) && max.map_or(true, |max| value <= max);
+                let message = format!(
+                    "{:?} = {:.4}, range [{}, {}]",
+                    metric,
+                    value,
+                    min.map_or(String::from("-"), |v| v.to_string()),
+                    max.map_or(String::from("-"), |v| v.to_string())
+                );
+                return Ok((passed, Some(value), message));
+            }
+            PlanStep::RunProfile { path, scurve } => {
+                return Self::run_profile(device, path, scurve.clone(), running);
+            }
+            PlanStep::Loop { .. } => bail!("loop must be handled by run_steps"),
+        }
+
+        Ok((true, None, String::new()))
+    }
+
+    fn sleep(ms: u64, running: &AtomicBool) {
+        let start = Instant::now();
+        while running.load(Ordering::SeqCst) && start.elapsed().as_millis() < ms as u128 {
+            thread::sleep(Duration::from_millis(ms.min(50)));
+        }
+    }
+
+    fn wait_stable(
+        motor: &MotorRef,
+        rps: f32,
+        tolerance: f32,
+        stable_ms: u64,
+        timeout_ms: u64,
+        sample_interval_ms: u32,
+        running: &AtomicBool,
+    ) -> Result<StepOutcome> {
+        let start = Instant::now();
+        let mut stable_since: Option<Instant> = None;
+        let mut curr_rps = 0.0;
+
+        while running.load(Ordering::SeqCst) {
+            let status = motor.lock().unwrap().get_motor_status()?;
+            if let Some(code) = status.error_code.filter(|code| *code > 0) {
+                let bits = decode_fault_bits(code);
+                return Ok((
+                    false,
+                    Some(code as f64),
+                    format!("fault: 0x{:x} [{}]", code, bits.join(", ")),
+                ));
+            }
+
+            curr_rps = motor.lock().unwrap().get_current_rps()?;
+            if libm::fabsf(curr_rps - rps) <= tolerance {
+                let since = *stable_since.get_or_insert_with(Instant::now);
+                if since.elapsed().as_millis() >= stable_ms as u128 {
+                    let elapsed = start.elapsed().as_millis() as f64;
+                    return Ok((true, Some(elapsed), format!("stable after {} ms", elapsed)));
+                }
+            } else {
+                stable_since = None;
+            }
+
+            if start.elapsed().as_millis() > timeout_ms as u128 {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(sample_interval_ms as u64));
+        }
+
+        Ok((
+            false,
+            Some(curr_rps as f64),
+            format!("timeout, current rps {:.3}", curr_rps),
+        ))
+    }
+
+    /// 在窗口内采样并计算遥测量
+    fn measure(
+        motor: &MotorRef,
+        metric: PlanMetric,
+        window_ms: u64,
+        sample_interval_ms: u32,
+        running: &AtomicBool,
+    ) -> Result<f64> {
+        if metric == PlanMetric::RsOnline {
+            Self::sleep(window_ms, running);
+            return Ok(motor.lock().unwrap().get_rs_online()? as f64);
+        }
+
+        let interval = Duration::from_millis(sample_interval_ms.max(1) as u64);
+        let start = Instant::now();
+        let mut values: Vec<f64> = Vec::new();
+        let mut error_code = 0;
+
+        loop {
+            match metric {
+                PlanMetric::Rps | PlanMetric::Jitter => {
+                    values.push(motor.lock().unwrap().get_current_rps()? as f64);
+                }
+                PlanMetric::VdcBus => {
+                    let params = motor.lock().unwrap().get_motor_params()?;
+                    values.push(params.vdc_bus.unwrap_or_default());
+                }
+                PlanMetric::ErrorCode => {
+                    let status = motor.lock().unwrap().get_motor_status()?;
+                    error_code |= status.error_code.unwrap_or_default();
+                }
+                PlanMetric::SupplyVoltage => {
+                    values.push(POWER_SUPPLY.lock().unwrap().measure_voltage()?);
+                }
+                PlanMetric::SupplyCurrent => {
+                    values.push(POWER_SUPPLY.lock().unwrap().measure_current()?);
+                }
+                PlanMetric::RsOnline => {}
+            }
+
+            if !running.load(Ordering::SeqCst) || start.elapsed().as_millis() >= window_ms as u128 {
+                break;
+            }
+            thread::sleep(interval);
+        }
+
+        let n = values.len().max(1) as f64;
+        let mean = values.iter().sum::<f64>() / n;
+
+        Ok(match metric {
+            PlanMetric::Jitter => {
+                libm::sqrt(values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n)
+            }
+            PlanMetric::ErrorCode => error_code as f64,
+            _ => mean,
+        })
+    }
+
+    /// 通过转速曲线执行器执行曲线, 等待其结束
+    fn run_profile(
+        device: &Device,
+        path: &str,
+        scurve: Option<SCurveParam>,
+        running: &AtomicBool,
+    ) -> Result<StepOutcome> {
+        let profile_runner = &device.tasks.profile_runner;
+        profile_runner.lock().unwrap().start(
+            device.motor.clone(),
+            ProfileRunParam {
+                path: path.to_string(),
+                looping: false,
+                loop_count: None,
+                update_interval_ms: 100,
+                record_path: None,
+                scurve,
+            },
+        )?;
+
+        loop {
+            let status = profile_runner.lock().unwrap().get_status()?;
+            match status.state.as_str() {
+                "finished" => return Ok((true, None, String::from("profile finished"))),
+                "aborted" => return Ok((false, None, String::from("profile aborted"))),
+                _ => {}
+            }
+
+            if !running.load(Ordering::SeqCst) {
+                profile_runner.lock().unwrap().abort();
+                return Ok((false, None, String::from("profile aborted")));
+            }
+
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    pub fn stop(&self) {
+        LOGGER.lock().unwrap().warning("停止测试计划");
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_result(&self) -> Result<PlanResult> {
+        Ok(self.result.lock().unwrap().clone())
+    }
+
+    /// 导出测试报告, json 包含测试计划和全部结果; csv 只包含各步骤结果
+    pub fn export_report(&self, path: &str) -> Result<()> {
+        let result = self.result.lock().unwrap();
+        let plan = self.plan.lock().unwrap();
+
+        let ext = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match ext.as_str() {
+            "json" => {
+                let report = PlanReport {
+                    plan: &plan,
+                    result: &result,
+                };
+                std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+            }
+            "csv" => {
+                let mut wtr = csv::Writer::from_path(path)?;
+                for step in result.steps.iter() {
+                    wtr.serialize(step)?;
+                }
+                wtr.flush()?;
+            }
+            _ => bail!("unsupported report format: {}", ext),
+        }
+
+        Ok(())
+    }
+}
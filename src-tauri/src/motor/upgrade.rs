@@ -1,37 +1,192 @@
+use crate::logger::LOGGER;
 use crate::motor::*;
+use crate::tools;
 use crate::ymodem::xymodem_util;
-use crate::ymodem::ymodem::Ymodem;
+use crate::ymodem::ymodem::{SendEvent, Ymodem};
 use anyhow::{bail, Ok, Result};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::BufReader;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 
+/// 进入 IAP 模式的最大尝试次数
+const MAX_IAP_ATTEMPTS: u32 = 200;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradePhase {
+    #[default]
+    Idle,
+    EnteringIap,  // 发送 update 命令, 等待设备进入 IAP 模式
+    Handshake,    // 等待 YMODEM 传输开始并确认起始帧
+    Transferring, // 发送数据块
+    Finalizing,   // 发送 EOT 和结束帧
+    Done,
+    Failed,
+    Cancelled,
+}
+
+/// 固件升级进度, 每次变化时通过 upgrade_progress 事件发送给前端
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct UpgradeProgress {
+    pub phase: UpgradePhase,
+    pub iap_attempts: u32,
+    pub block: u32,
+    pub total_blocks: u32,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub retries: u32,
+    pub percent: f32,
+    pub error: Option<String>,
+}
+
+/// 固件升级在后台线程中执行, 可以取消
 pub struct Upgrade {
-    pub progress: Arc<Mutex<f32>>,
+    pub progress: Arc<Mutex<UpgradeProgress>>,
+    running: Arc<AtomicBool>,
+    cancel: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
 }
 
 pub static UPGRADE: Lazy<Mutex<Upgrade>> = Lazy::new(|| Mutex::new(Upgrade::new()));
 
+/// 更新进度并通知前端
+fn report(progress: &Mutex<UpgradeProgress>, update: impl FnOnce(&mut UpgradeProgress)) {
+    let snapshot = {
+        let mut progress = progress.lock().unwrap();
+        update(&mut progress);
+        progress.clone()
+    };
+
+    LOGGER.lock().unwrap().emit("upgrade_progress", snapshot);
+}
+
 impl Upgrade {
     pub fn new() -> Self {
         Upgrade {
-            progress: Arc::new(Mutex::new(0.0)),
+            progress: Arc::new(Mutex::new(UpgradeProgress::default())),
+            running: Arc::new(AtomicBool::new(false)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
         }
     }
 
+    pub fn get_progress(&self) -> UpgradeProgress {
+        self.progress.lock().unwrap().clone()
+    }
+
     pub fn start(&self, sp: &str, baud: u32, fw_path: &str) -> Result<()> {
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("firmware upgrade is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        if !tools::is_file_exist(fw_path) {
+            bail!("File: {} not exist", fw_path);
+        }
+
+        let progress = Arc::clone(&self.progress);
+        let running = Arc::clone(&self.running);
+        let cancel = Arc::clone(&self.cancel);
+        let (sp, fw_path) = (sp.to_string(), fw_path.to_string());
+
+        *progress.lock().unwrap() = UpgradeProgress {
+            phase: UpgradePhase::EnteringIap,
+            ..Default::default()
+        };
+        running.store(true, Ordering::SeqCst);
+        cancel.store(false, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            match Self::run(&progress, &cancel, &sp, baud, &fw_path) {
+                core::result::Result::Ok(_) => {
+                    LOGGER.lock().unwrap().info("固件升级完成");
+                    report(&progress, |p| {
+                        p.phase = UpgradePhase::Done;
+                        p.percent = 100.0;
+                    });
+                }
+                Err(_) if cancel.load(Ordering::SeqCst) => {
+                    // 进入 IAP 模式之前取消时原固件不受影响, 之后取消时设备停留在 IAP 模式等待重新升级
+                    let entered_iap = progress.lock().unwrap().phase != UpgradePhase::EnteringIap;
+                    let message = if entered_iap {
+                        "upgrade cancelled, device stays in IAP mode until upgraded again"
+                    } else {
+                        "upgrade cancelled, device firmware unchanged"
+                    };
+                    LOGGER
+                        .lock()
+                        .unwrap()
+                        .warning(format!("固件升级已取消: {}", message).as_str());
+                    report(&progress, |p| {
+                        p.phase = UpgradePhase::Cancelled;
+                        p.error = Some(message.to_string());
+                    });
+                }
+                Err(err) => {
+                    LOGGER
+                        .lock()
+                        .unwrap()
+                        .danger(format!("固件升级失败: {}", err).as_str());
+                    report(&progress, |p| {
+                        p.phase = UpgradePhase::Failed;
+                        p.error = Some(err.to_string());
+                    });
+                }
+            }
+
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    /// 取消升级, 传输过程中会向设备发送 CAN 终止 YMODEM 传输;
+    /// 返回升级线程, 由调用者在释放 UPGRADE 后等待其退出, 避免阻塞进度查询
+    pub fn cancel(&self) -> Option<thread::JoinHandle<()>> {
+        self.cancel.store(true, Ordering::SeqCst);
+        self.handle.lock().unwrap().take()
+    }
+
+    fn run(
+        progress: &Arc<Mutex<UpgradeProgress>>,
+        cancel: &AtomicBool,
+        sp: &str,
+        baud: u32,
+        fw_path: &str,
+    ) -> Result<()> {
         // 打开文件
-        let file = File::open(fw_path).unwrap();
-        let file_len = file.metadata().unwrap().len();
+        let file = File::open(fw_path)?;
+        let file_len = file.metadata()?.len();
         let path = std::path::Path::new(fw_path);
-        let filename = path.file_name().unwrap();
+        let filename = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => bail!("path is invalid"),
+        };
         // 创建一个BufReader来读取文件
         let mut reader = BufReader::new(file);
 
-        // 释放MOTOR串口
-        MOTOR.lock().unwrap().port = None;
+        report(progress, |p| {
+            *p = UpgradeProgress {
+                phase: UpgradePhase::EnteringIap,
+                total_bytes: file_len,
+                total_blocks: file_len.div_ceil(1024) as u32,
+                ..Default::default()
+            }
+        });
+
+        // 释放占用该串口的电机
+        release_port(sp);
         // 重新打开串口
         let mut port = serialport::new(sp, baud)
             .timeout(Duration::from_millis(200))
@@ -42,7 +197,11 @@ impl Upgrade {
         let mut connect_cnt = 0;
 
         loop {
-            port.write(&cmd).unwrap();
+            if cancel.load(Ordering::SeqCst) {
+                bail!("upgrade cancelled");
+            }
+
+            port.write(&cmd)?;
             match (xymodem_util::get_stream_timeout(&mut port))? {
                 Some(c) => {
                     if c[3] == crate::ymodem::ymodem::CRC {
@@ -54,20 +213,42 @@ impl Upgrade {
             }
 
             connect_cnt += 1;
+            report(progress, |p| p.iap_attempts = connect_cnt);
 
-            if connect_cnt >= 200 {
-                eprint!("Enter IAP Mode Timeout",);
-                break;
+            if connect_cnt >= MAX_IAP_ATTEMPTS {
+                bail!("enter IAP mode timeout");
             }
         }
 
         let mut ymodem = Ymodem::new();
 
+        let mut on_event = |event: SendEvent| match event {
+            SendEvent::Handshake => report(progress, |p| p.phase = UpgradePhase::Handshake),
+            SendEvent::Block {
+                block,
+                total_blocks,
+                bytes_sent,
+                retries,
+            } => report(progress, |p| {
+                p.phase = UpgradePhase::Transferring;
+                p.block = block;
+                p.total_blocks = total_blocks;
+                p.bytes_sent = bytes_sent;
+                p.retries = retries;
+                if file_len > 0 {
+                    p.percent = bytes_sent as f32 * 100.0 / file_len as f32;
+                }
+            }),
+            SendEvent::Finalizing => report(progress, |p| p.phase = UpgradePhase::Finalizing),
+        };
+
         match ymodem.send(
             &mut port,
             &mut reader,
-            filename.to_str().unwrap().to_string(),
+            filename,
             file_len,
+            &mut on_event,
+            cancel,
         ) {
             core::result::Result::Ok(_) => {}
             Err(err) => {
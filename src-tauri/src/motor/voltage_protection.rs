@@ -0,0 +1,410 @@
+use crate::logger::LOGGER;
+use crate::motor::*;
+use crate::tools::POWER_SUPPLY;
+use anyhow::{bail, Ok, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 故障码中的过压、欠压位, 与 FAULT_BITS 的顺序一致
+const OVER_VOLTAGE_BIT: u16 = 1 << 0;
+const UNDER_VOLTAGE_BIT: u16 = 1 << 1;
+
+fn default_step_v() -> f64 {
+    0.2
+}
+
+fn default_dwell_ms() -> u64 {
+    500
+}
+
+fn default_spin_up_ms() -> u64 {
+    3000
+}
+
+fn default_threshold_tolerance() -> f64 {
+    1.0
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct VoltageProtectionParam {
+    pub target_rps: f32,
+    pub nominal_voltage: f64, // 额定电压, 扫描从该电压开始, 结束后恢复
+    pub min_voltage: f64,     // 欠压扫描的下限
+    pub max_voltage: f64,     // 过压扫描的上限
+    #[serde(default = "default_step_v")]
+    pub step_v: f64,
+    #[serde(default = "default_dwell_ms")]
+    pub dwell_ms: u64, // 每个电压点的停留时间
+    #[serde(default = "default_spin_up_ms")]
+    pub spin_up_ms: u64, // 启动电机后等待达速的时间
+    #[serde(default = "default_threshold_tolerance")]
+    pub tolerance: f64, // 保护点与配置值允许的偏差(V)
+    #[serde(default)]
+    pub current_limit: Option<f64>,
+}
+
+/// 扫描过程中的一个电压点
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct VoltageSweepPoint {
+    pub phase: String,
+    pub supply_voltage: f64,
+    pub vdc_bus: Option<f64>,
+    pub rps: Option<f32>,
+    pub error_code: Option<u16>,
+}
+
+/// 单个保护点的检查结果
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct VoltageThresholdCheck {
+    pub configured: Option<f32>, // MotorFaultChkParams 中配置的保护值
+    pub trip_vdc: Option<f64>,   // 报故障时电机上报的母线电压
+    pub trip_supply_voltage: Option<f64>, // 报故障时电源的设定电压
+    pub deviation: Option<f64>,
+    pub passed: bool,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct VoltageProtectionResult {
+    pub running: bool,
+    pub phase: String,
+    pub supply_voltage: Option<f64>,
+    pub under_voltage: Option<VoltageThresholdCheck>,
+    pub over_voltage: Option<VoltageThresholdCheck>,
+    pub passed: Option<bool>,
+    pub error: Option<String>,
+    pub points: Vec<VoltageSweepPoint>,
+}
+
+/// 一个方向的电压扫描: 以 step 调节电压直到电机报出 fault_bit 对应的故障或到达 limit
+struct SweepPhase {
+    name: &'static str,
+    step: f64,
+    limit: f64,
+    fault_bit: u16,
+    configured: Option<f32>,
+}
+
+/// 欠压/过压保护验证: 电机运行时缓慢调节程控电源电压, 记录电机报欠压、过压故障时的母线电压
+pub struct VoltageProtectionHandle {
+    running: Arc<AtomicBool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+    result: Arc<Mutex<VoltageProtectionResult>>,
+}
+
+impl VoltageProtectionHandle {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            handle: Mutex::new(None),
+            result: Arc::new(Mutex::new(VoltageProtectionResult::default())),
+        }
+    }
+
+    pub fn start(&self, motor: MotorRef, param: VoltageProtectionParam) -> Result<()> {
+        if param.step_v <= 0.0 {
+            bail!("voltage step must be greater than 0");
+        }
+
+        if !(param.min_voltage < param.nominal_voltage && param.nominal_voltage < param.max_voltage)
+        {
+            bail!("nominal voltage must be between min and max voltage");
+        }
+
+        if motor.lock().unwrap().port.is_none() {
+            bail!("motor port is not connected");
+        }
+
+        if !POWER_SUPPLY.lock().unwrap().is_connected() {
+            bail!("power supply is not connected");
+        }
+
+        let mut handle_guard = self.handle.lock().unwrap();
+        if self.running.load(Ordering::SeqCst) {
+            bail!("voltage protection test is running");
+        }
+
+        if let Some(handle) = handle_guard.take() {
+            handle.join().unwrap();
+        }
+
+        let faultchk = motor.lock().unwrap().get_motor_faultchk_params()?;
+
+        *self.result.lock().unwrap() = VoltageProtectionResult {
+            running: true,
+            ..Default::default()
+        };
+
+        let running = Arc::clone(&self.running);
+        let result = Arc::clone(&self.result);
+        running.store(true, Ordering::SeqCst);
+
+        let handle = thread::spawn(move || {
+            LOGGER.lock().unwrap().info(
+                format!(
+                    "开始欠压/过压保护测试, 配置欠压 {:?}V, 过压 {:?}V",
+                    faultchk.under_voltage, faultchk.over_voltage
+                )
+                .as_str(),
+            );
+
+            let tested = Self::run(&motor, &param, &faultchk, &running, &result);
+
+            // 无论成功与否都停机并恢复额定电压
+            motor.lock().unwrap().stop_motor().ok();
+            POWER_SUPPLY
+                .lock()
+                .unwrap()
+                .set_voltage(param.nominal_voltage)
+                .ok();
+
+            let mut r = result.lock().unwrap();
+            match tested {
+                core::result::Result::Ok(_) => {
+                    // 测试被停止时两个保护点未全部测完, 不给出结论
+                    r.passed = match (&r.under_voltage, &r.over_voltage) {
+                        (Some(under), Some(over)) => Some(under.passed && over.passed),
+                        _ => None,
+                    };
+                    LOGGER.lock().unwrap().warning(
+                        format!(
+                            "欠压/过压保护测试结束: {}",
+                            match r.passed {
+                                Some(true) => "通过",
+                                Some(false) => "失败",
+                                None => "已停止",
+                            }
+                        )
+                        .as_str(),
+                    );
+                }
+                Err(err) => {
+                    LOGGER
+                        .lock()
+                        .unwrap()
+                        .danger(format!("欠压/过压保护测试中止: {}", err).as_str());
+                    r.error = Some(err.to_string());
+                }
+            }
+            r.phase = String::new();
+            r.running = false;
+            running.store(false, Ordering::SeqCst);
+        });
+
+        *handle_guard = Some(handle);
+
+        Ok(())
+    }
+
+    fn run(
+        motor: &MotorRef,
+        param: &VoltageProtectionParam,
+        faultchk: &MotorFaultChkParams,
+        running: &AtomicBool,
+        result: &Mutex<VoltageProtectionResult>,
+    ) -> Result<()> {
+        {
+            let mut supply = POWER_SUPPLY.lock().unwrap();
+            if let Some(current) = param.current_limit {
+                supply.set_current_limit(current)?;
+            }
+            supply.set_voltage(param.nominal_voltage)?;
+            supply.set_output(true)?;
+        }
+
+        // 欠压: 从额定电压逐步降低
+        if !Self::spin_up(motor, param, running)? {
+            return Ok(());
+        }
+        let phase = SweepPhase {
+            name: "under_voltage",
+            step: -param.step_v,
+            limit: param.min_voltage,
+            fault_bit: UNDER_VOLTAGE_BIT,
+            configured: faultchk.under_voltage,
+        };
+        let under = Self::sweep(motor, param, &phase, running, result)?;
+        result.lock().unwrap().under_voltage = under;
+
+        // 恢复额定电压后重新启动, 再逐步升高
+        POWER_SUPPLY
+            .lock()
+            .unwrap()
+            .set_voltage(param.nominal_voltage)?;
+        if !Self::sleep(param.dwell_ms, running) {
+            return Ok(());
+        }
+
+        if !Self::spin_up(motor, param, running)? {
+            return Ok(());
+        }
+        let phase = SweepPhase {
+            name: "over_voltage",
+            step: param.step_v,
+            limit: param.max_voltage,
+            fault_bit: OVER_VOLTAGE_BIT,
+            configured: faultchk.over_voltage,
+        };
+        let over = Self::sweep(motor, param, &phase, running, result)?;
+        result.lock().unwrap().over_voltage = over;
+
+        Ok(())
+    }
+
+    /// 通信失败时电机状态读回的故障码为 0, 与"未报故障"无法区分, 读取状态前先确认控制器应答
+    fn check_responding(motor: &MotorRef, voltage: f64) -> Result<()> {
+        if motor.lock().unwrap().probe_version().is_none() {
+            bail!("motor not responding at {:.2}V", voltage);
+        }
+        Ok(())
+    }
+
+    /// 清除故障并启动电机, 等待达速, 测试被停止时返回 false
+    fn spin_up(
+        motor: &MotorRef,
+        param: &VoltageProtectionParam,
+        running: &AtomicBool,
+    ) -> Result<bool> {
+        {
+            let mut motor = motor.lock().unwrap();
+            motor.stop_motor()?;
+            motor.clear_motor_faults()?;
+            motor.update_motor_speed_rps((param.target_rps * 100000.0) as u32)?;
+        }
+        thread::sleep(Duration::from_millis(100));
+        motor.lock().unwrap().start_motor()?;
+
+        if !Self::sleep(param.spin_up_ms, running) {
+            return Ok(false);
+        }
+
+        Self::check_responding(motor, param.nominal_voltage)?;
+        let status = motor.lock().unwrap().get_motor_status()?;
+        let error_code = status.error_code.unwrap_or_default();
+        if error_code != 0 {
+            bail!(
+                "motor fault before sweep: {}",
+                decode_fault_bits(error_code).join("|")
+            );
+        }
+
+        Ok(true)
+    }
+
+    /// 按 phase 扫描电压并与配置的保护值比较, 测试被中止时返回 None
+    fn sweep(
+        motor: &MotorRef,
+        param: &VoltageProtectionParam,
+        phase: &SweepPhase,
+        running: &AtomicBool,
+        result: &Mutex<VoltageProtectionResult>,
+    ) -> Result<Option<VoltageThresholdCheck>> {
+        result.lock().unwrap().phase = phase.name.to_string();
+
+        let configured = phase.configured;
+        let mut check = VoltageThresholdCheck {
+            configured,
+            ..Default::default()
+        };
+
+        let mut voltage = param.nominal_voltage;
+        loop {
+            voltage += phase.step;
+            if (phase.step < 0.0 && voltage < phase.limit)
+                || (phase.step > 0.0 && voltage > phase.limit)
+            {
+                check.message = format!("no fault reported before reaching {:.2}V", phase.limit);
+                break;
+            }
+
+            POWER_SUPPLY.lock().unwrap().set_voltage(voltage)?;
+            result.lock().unwrap().supply_voltage = Some(voltage);
+            if !Self::sleep(param.dwell_ms, running) {
+                return Ok(None);
+            }
+
+            // 控制器掉电或停止应答时中止扫描, 由调用者恢复额定电压并报告错误
+            Self::check_responding(motor, voltage)?;
+            let (vdc_bus, rps, error_code) = {
+                let mut motor = motor.lock().unwrap();
+                let status = motor.get_motor_status()?;
+                let vdc_bus = motor.get_motor_params()?.vdc_bus;
+                let rps = motor.get_current_rps().ok();
+                (vdc_bus, rps, status.error_code)
+            };
+
+            result.lock().unwrap().points.push(VoltageSweepPoint {
+                phase: phase.name.to_string(),
+                supply_voltage: voltage,
+                vdc_bus,
+                rps,
+                error_code,
+            });
+
+            let error_code = error_code.unwrap_or_default();
+            if error_code & phase.fault_bit != 0 {
+                check.trip_vdc = vdc_bus;
+                check.trip_supply_voltage = Some(voltage);
+                break;
+            } else if error_code != 0 {
+                check.message = format!(
+                    "unexpected fault at {:.2}V: {}",
+                    voltage,
+                    decode_fault_bits(error_code).join("|")
+                );
+                break;
+            }
+        }
+
+        let trip = check.trip_vdc.or(check.trip_supply_voltage);
+        match (trip, configured) {
+            (Some(trip), Some(configured)) => {
+                let deviation = trip - configured as f64;
+                check.deviation = Some(deviation);
+                check.passed = deviation.abs() <= param.tolerance;
+                check.message = format!(
+                    "tripped at {:.2}V, configured {:.2}V, deviation {:+.2}V",
+                    trip, configured, deviation
+                );
+            }
+            (Some(trip), None) => {
+                check.message = format!("tripped at {:.2}V, no configured value", trip);
+            }
+            _ => {}
+        }
+
+        let log = format!("{}: {}", phase.name, check.message);
+        if check.passed {
+            LOGGER.lock().unwrap().info(log.as_str());
+        } else {
+            LOGGER.lock().unwrap().danger(log.as_str());
+        }
+
+        Ok(Some(check))
+    }
+
+    /// 等待 ms 毫秒, 测试被停止时返回 false
+    fn sleep(ms: u64, running: &AtomicBool) -> bool {
+        let start = Instant::now();
+        while running.load(Ordering::SeqCst) && start.elapsed().as_millis() < ms as u128 {
+            thread::sleep(Duration::from_millis(ms.min(50)));
+        }
+
+        running.load(Ordering::SeqCst)
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.join().unwrap();
+        }
+    }
+
+    pub fn get_result(&self) -> Result<VoltageProtectionResult> {
+        Ok(self.result.lock().unwrap().clone())
+    }
+}
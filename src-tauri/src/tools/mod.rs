@@ -1,5 +1,13 @@
-mod utils;
+mod modbus;
+mod power_switch;
+mod power_supply;
 mod relay;
+mod usb_relay;
+mod utils;
 
+pub use self::modbus::*;
+pub use self::power_switch::*;
+pub use self::power_supply::*;
+pub use self::relay::*;
+pub use self::usb_relay::*;
 pub use self::utils::*;
-pub use self::relay::*;
\ No newline at end of file
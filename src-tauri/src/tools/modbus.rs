@@ -0,0 +1,333 @@
+use anyhow::{bail, Ok, Result};
+use serialport::{ClearBuffer, SerialPort};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+const FC_READ_COILS: u8 = 0x01;
+const FC_WRITE_SINGLE_COIL: u8 = 0x05;
+const FC_WRITE_MULTIPLE_COILS: u8 = 0x0F;
+
+/// 等待从站应答的超时时间
+const RESPONSE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Modbus 传输层, RTU(串口) 和 TCP 共用同一套功能码实现
+pub trait ModbusTransport {
+    /// 发送请求并读取应答, 返回应答中功能码之后的数据
+    ///
+    /// data_len 为正常应答的数据长度, 异常应答(功能码最高位置 1)返回异常码错误
+    fn transact(&mut self, function: u8, data: &[u8], data_len: usize) -> Result<Vec<u8>>;
+}
+
+/// CRC-16/MODBUS 校验
+pub fn modbus_crc16(data: &[u8]) -> u16 {
+    let crc_engine = crc::Crc::<u16>::new(&crc::CRC_16_MODBUS);
+    crc_engine.checksum(data)
+}
+
+/// 组帧: 从站地址 + 功能码 + 数据 + CRC(低字节在前)
+pub fn modbus_frame(slave: u8, function: u8, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 4);
+    frame.push(slave);
+    frame.push(function);
+    frame.extend_from_slice(data);
+
+    let crc = modbus_crc16(&frame);
+    frame.push((crc & 0xff) as u8);
+    frame.push((crc >> 8) as u8);
+    frame
+}
+
+/// 读线圈(功能码 01), 返回 start 开始的 count 个线圈状态
+pub fn modbus_read_coils(
+    bus: &mut dyn ModbusTransport,
+    start: u16,
+    count: u16,
+) -> Result<Vec<bool>> {
+    if count == 0 || count > 2000 {
+        bail!("invalid coil count {}", count);
+    }
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&start.to_be_bytes());
+    data.extend_from_slice(&count.to_be_bytes());
+
+    let byte_cnt = count.div_ceil(8) as usize;
+    let resp = bus.transact(FC_READ_COILS, &data, 1 + byte_cnt)?;
+    if resp[0] as usize != byte_cnt || resp.len() != 1 + byte_cnt {
+        bail!("unexpected byte count {} in read coils response", resp[0]);
+    }
+
+    Ok((0..count as usize)
+        .map(|i| resp[1 + i / 8] & (1 << (i % 8)) != 0)
+        .collect())
+}
+
+/// 写单个线圈(功能码 05)
+pub fn modbus_write_single_coil(
+    bus: &mut dyn ModbusTransport,
+    address: u16,
+    on: bool,
+) -> Result<()> {
+    let value: u16 = if on { 0xFF00 } else { 0x0000 };
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&address.to_be_bytes());
+    data.extend_from_slice(&value.to_be_bytes());
+
+    // 正常应答为请求的回显
+    let resp = bus.transact(FC_WRITE_SINGLE_COIL, &data, 4)?;
+    if resp != data {
+        bail!("unexpected write single coil response");
+    }
+
+    Ok(())
+}
+
+/// 写多个线圈(功能码 0F)
+pub fn modbus_write_multiple_coils(
+    bus: &mut dyn ModbusTransport,
+    start: u16,
+    values: &[bool],
+) -> Result<()> {
+    if values.is_empty() || values.len() > 1968 {
+        bail!("invalid coil count {}", values.len());
+    }
+
+    let count = values.len() as u16;
+    let mut bytes = vec![0u8; values.len().div_ceil(8)];
+    for (i, on) in values.iter().enumerate() {
+        if *on {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&start.to_be_bytes());
+    data.extend_from_slice(&count.to_be_bytes());
+    data.push(bytes.len() as u8);
+    data.extend_from_slice(&bytes);
+
+    // 正常应答为起始地址和线圈数量
+    let resp = bus.transact(FC_WRITE_MULTIPLE_COILS, &data, 4)?;
+    if resp != data[..4] {
+        bail!("unexpected write multiple coils response");
+    }
+
+    Ok(())
+}
+
+/// Modbus RTU 主站, 借用已打开的串口
+pub struct ModbusRtu<'a> {
+    pub port: &'a mut dyn SerialPort,
+    pub slave: u8,
+}
+
+impl ModbusTransport for ModbusRtu<'_> {
+    fn transact(&mut self, function: u8, data: &[u8], data_len: usize) -> Result<Vec<u8>> {
+        self.port.clear(ClearBuffer::Input)?;
+        self.port
+            .write_all(&modbus_frame(self.slave, function, data))?;
+        self.port.flush()?;
+
+        let mut header = [0u8; 2];
+        read_exact(&mut self.port, &mut header)?;
+        if header[0] != self.slave {
+            bail!("unexpected slave address 0x{:02x} in response", header[0]);
+        }
+
+        let body_len = if header[1] == function | 0x80 {
+            1
+        } else if header[1] == function {
+            data_len
+        } else {
+            bail!("unexpected function code 0x{:02x} in response", header[1]);
+        };
+
+        let mut body = vec![0u8; body_len + 2];
+        read_exact(&mut self.port, &mut body)?;
+
+        let mut frame = header.to_vec();
+        frame.extend_from_slice(&body[..body_len]);
+        let crc = u16::from_le_bytes([body[body_len], body[body_len + 1]]);
+        if crc != modbus_crc16(&frame) {
+            bail!("modbus response crc mismatch");
+        }
+
+        if header[1] & 0x80 != 0 {
+            bail!("modbus exception code 0x{:02x}", body[0]);
+        }
+
+        body.truncate(body_len);
+        Ok(body)
+    }
+}
+
+/// Modbus TCP 主站, 请求前加 MBAP 报文头, 不带 CRC
+pub struct ModbusTcp {
+    stream: TcpStream,
+    unit_id: u8,
+    transaction_id: u16,
+}
+
+impl ModbusTcp {
+    pub fn connect(host: &str, port: u16, unit_id: u8) -> Result<Self> {
+        let addr = match std::net::ToSocketAddrs::to_socket_addrs(&(host, port))?.next() {
+            Some(addr) => addr,
+            None => bail!("invalid address {}:{}", host, port),
+        };
+
+        let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(2))?;
+        stream.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+        stream.set_write_timeout(Some(RESPONSE_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+
+        Ok(ModbusTcp {
+            stream,
+            unit_id,
+            transaction_id: 0,
+        })
+    }
+}
+
+impl ModbusTransport for ModbusTcp {
+    fn transact(&mut self, function: u8, data: &[u8], _data_len: usize) -> Result<Vec<u8>> {
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+
+        // MBAP: 事务号 + 协议号(0) + 后续长度 + 单元号
+        let mut frame = Vec::with_capacity(data.len() + 8);
+        frame.extend_from_slice(&self.transaction_id.to_be_bytes());
+        frame.extend_from_slice(&0u16.to_be_bytes());
+        frame.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        frame.push(self.unit_id);
+        frame.push(function);
+        frame.extend_from_slice(data);
+        self.stream.write_all(&frame)?;
+
+        let mut header = [0u8; 8];
+        self.stream.read_exact(&mut header)?;
+        let transaction_id = u16::from_be_bytes([header[0], header[1]]);
+        let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+        if transaction_id != self.transaction_id || len < 2 {
+            bail!("unexpected modbus tcp response header");
+        }
+
+        let mut body = vec![0u8; len - 2];
+        self.stream.read_exact(&mut body)?;
+
+        if header[7] == function | 0x80 {
+            bail!(
+                "modbus exception code 0x{:02x}",
+                body.first().copied().unwrap_or_default()
+            );
+        } else if header[7] != function {
+            bail!("unexpected function code 0x{:02x} in response", header[7]);
+        }
+
+        Ok(body)
+    }
+}
+
+fn read_exact(port: &mut &mut dyn SerialPort, buf: &mut [u8]) -> Result<()> {
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    let mut read = 0;
+
+    while read < buf.len() {
+        match port.read(&mut buf[read..]) {
+            core::result::Result::Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(e) => bail!(e),
+        }
+
+        if read < buf.len() && Instant::now() > deadline {
+            bail!("modbus response timeout");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 回放固定应答并记录请求的传输层
+    struct MockTransport {
+        request: Vec<u8>,
+        response: Vec<u8>,
+    }
+
+    impl ModbusTransport for MockTransport {
+        fn transact(&mut self, function: u8, data: &[u8], _data_len: usize) -> Result<Vec<u8>> {
+            self.request = [&[function], data].concat();
+            Ok(self.response.clone())
+        }
+    }
+
+    #[test]
+    fn crc16_known_vector() {
+        assert_eq!(modbus_crc16(&[0x01, 0x05, 0x00, 0x00, 0xFF, 0x00]), 0x3A8C);
+    }
+
+    #[test]
+    fn frame_appends_crc_low_byte_first() {
+        assert_eq!(
+            modbus_frame(0x01, FC_WRITE_SINGLE_COIL, &[0x00, 0x00, 0xFF, 0x00]),
+            vec![0x01, 0x05, 0x00, 0x00, 0xFF, 0x00, 0x8C, 0x3A]
+        );
+        assert_eq!(
+            modbus_frame(0x01, FC_WRITE_SINGLE_COIL, &[0x00, 0x00, 0x00, 0x00]),
+            vec![0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0xCD, 0xCA]
+        );
+    }
+
+    #[test]
+    fn read_coils_unpacks_lsb_first() {
+        let mut bus = MockTransport {
+            request: Vec::new(),
+            response: vec![0x02, 0b1000_0101, 0b0000_0010],
+        };
+
+        let coils = modbus_read_coils(&mut bus, 0, 10).unwrap();
+        assert_eq!(bus.request, vec![FC_READ_COILS, 0x00, 0x00, 0x00, 0x0A]);
+        assert_eq!(
+            coils,
+            vec![true, false, true, false, false, false, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn read_coils_rejects_wrong_byte_count() {
+        let mut bus = MockTransport {
+            request: Vec::new(),
+            response: vec![0x01, 0xFF],
+        };
+
+        assert!(modbus_read_coils(&mut bus, 0, 10).is_err());
+        assert!(modbus_read_coils(&mut bus, 0, 0).is_err());
+    }
+
+    #[test]
+    fn write_multiple_coils_packs_lsb_first() {
+        let mut bus = MockTransport {
+            request: Vec::new(),
+            response: vec![0x00, 0x00, 0x00, 0x09],
+        };
+
+        let values = [true, false, false, true, false, false, false, false, true];
+        modbus_write_multiple_coils(&mut bus, 0, &values).unwrap();
+        assert_eq!(
+            bus.request,
+            vec![
+                FC_WRITE_MULTIPLE_COILS,
+                0x00,
+                0x00,
+                0x00,
+                0x09,
+                0x02,
+                0b0000_1001,
+                0x01
+            ]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{bail, Ok, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serialport::{ClearBuffer, SerialPort};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 等待查询应答的超时时间
+const QUERY_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// 程控电源的连接方式, 串口或 LAN(SCPI raw socket, 一般为 5025 端口)
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PowerSupplyLink {
+    Serial { sp: String, baud: u32 },
+    Tcp { host: String, port: Option<u16> },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Default)]
+pub struct PowerSupplyStatus {
+    pub connected: bool,
+    pub idn: Option<String>,
+    pub output: Option<bool>,
+    pub set_voltage: Option<f64>,
+    pub current_limit: Option<f64>,
+    pub voltage: Option<f64>, // 实测输出电压
+    pub current: Option<f64>, // 实测输出电流
+}
+
+enum ScpiLink {
+    Serial(Box<dyn SerialPort>),
+    Tcp(TcpStream),
+}
+
+impl ScpiLink {
+    fn write_line(&mut self, cmd: &str) -> Result<()> {
+        let line = format!("{}\n", cmd);
+        match self {
+            ScpiLink::Serial(port) => {
+                port.clear(ClearBuffer::Input)?;
+                port.write_all(line.as_bytes())?;
+                port.flush()?;
+            }
+            ScpiLink::Tcp(stream) => stream.write_all(line.as_bytes())?,
+        }
+
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        let mut resp = Vec::new();
+        let mut buf = [0u8; 64];
+        while !resp.ends_with(b"\n") {
+            let result = match self {
+                ScpiLink::Serial(port) => port.read(&mut buf),
+                ScpiLink::Tcp(stream) => stream.read(&mut buf),
+            };
+
+            match result {
+                core::result::Result::Ok(0) => bail!("power supply connection closed"),
+                core::result::Result::Ok(n) => resp.extend_from_slice(&buf[..n]),
+                Err(e)
+                    if e.kind() == std::io::ErrorKind::TimedOut
+                        || e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => bail!(e),
+            }
+
+            if !resp.ends_with(b"\n") && Instant::now() > deadline {
+                bail!("power supply response timeout");
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&resp).trim().to_string())
+    }
+}
+
+/// SCPI 程控电源, 设置输出电压、限流, 开关输出并读取实测电压电流
+pub struct PowerSupply {
+    link: Option<ScpiLink>,
+    idn: Option<String>,
+}
+
+pub static POWER_SUPPLY: Lazy<Mutex<PowerSupply>> = Lazy::new(|| Mutex::new(PowerSupply::new()));
+
+impl PowerSupply {
+    pub fn new() -> Self {
+        PowerSupply {
+            link: None,
+            idn: None,
+        }
+    }
+
+    /// 连接电源并读取型号, 读取失败说明电源未应答
+    pub fn connect(&mut self, link: &PowerSupplyLink) -> Result<String> {
+        let link = match link {
+            PowerSupplyLink::Serial { sp, baud } => ScpiLink::Serial(
+                serialport::new(sp, *baud)
+                    .timeout(Duration::from_millis(50))
+                    .open()?,
+            ),
+            PowerSupplyLink::Tcp { host, port } => {
+                let addr = match std::net::ToSocketAddrs::to_socket_addrs(&(
+                    host.as_str(),
+                    port.unwrap_or(5025),
+                ))?
+                .next()
+                {
+                    Some(addr) => addr,
+                    None => bail!("invalid address {}", host),
+                };
+
+                let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(2))?;
+                stream.set_read_timeout(Some(Duration::from_millis(50)))?;
+                ScpiLink::Tcp(stream)
+            }
+        };
+
+        self.link = Some(link);
+        match self.query("*IDN?") {
+            core::result::Result::Ok(idn) => {
+                self.idn = Some(idn.clone());
+                Ok(idn)
+            }
+            Err(err) => {
+                self.disconnect();
+                bail!("power supply not responding: {}", err)
+            }
+        }
+    }
+
+    pub fn disconnect(&mut self) {
+        self.link = None;
+        self.idn = None;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.link.is_some()
+    }
+
+    pub fn set_voltage(&mut self, voltage: f64) -> Result<()> {
+        self.write(format!("VOLT {:.3}", voltage).as_str())
+    }
+
+    pub fn set_current_limit(&mut self, current: f64) -> Result<()> {
+        self.write(format!("CURR {:.3}", current).as_str())
+    }
+
+    pub fn set_output(&mut self, on: bool) -> Result<()> {
+        self.write(if on { "OUTP ON" } else { "OUTP OFF" })
+    }
+
+    pub fn get_output(&mut self) -> Result<bool> {
+        let resp = self.query("OUTP?")?;
+        match resp.to_uppercase().as_str() {
+            "1" | "ON" => Ok(true),
+            "0" | "OFF" => Ok(false),
+            _ => bail!("unexpected output state {}", resp),
+        }
+    }
+
+    pub fn measure_voltage(&mut self) -> Result<f64> {
+        self.query_f64("MEAS:VOLT?")
+    }
+
+    pub fn measure_current(&mut self) -> Result<f64> {
+        self.query_f64("MEAS:CURR?")
+    }
+
+    /// 读取电源状态, 未连接时只返回 connected = false
+    pub fn get_status(&mut self) -> Result<PowerSupplyStatus> {
+        if !self.is_connected() {
+            return Ok(PowerSupplyStatus::default());
+        }
+
+        Ok(PowerSupplyStatus {
+            connected: true,
+            idn: self.idn.clone(),
+            output: Some(self.get_output()?),
+            set_voltage: Some(self.query_f64("VOLT?")?),
+            current_limit: Some(self.query_f64("CURR?")?),
+            voltage: Some(self.measure_voltage()?),
+            current: Some(self.measure_current()?),
+        })
+    }
+
+    fn link_mut(&mut self) -> Result<&mut ScpiLink> {
+        match self.link {
+            Some(ref mut link) => Ok(link),
+            None => bail!("power supply is not connected"),
+        }
+    }
+
+    fn write(&mut self, cmd: &str) -> Result<()> {
+        self.link_mut()?.write_line(cmd)
+    }
+
+    fn query(&mut self, cmd: &str) -> Result<String> {
+        let link = self.link_mut()?;
+        link.write_line(cmd)?;
+        link.read_line()
+    }
+
+    fn query_f64(&mut self, cmd: &str) -> Result<f64> {
+        let resp = self.query(cmd)?;
+        match resp.parse::<f64>() {
+            core::result::Result::Ok(value) => Ok(value),
+            Err(_) => bail!("unexpected response {} to {}", resp, cmd),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::logger::LOGGER;
+use crate::tools::*;
+use anyhow::{bail, Ok, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// 电机供电开关, 启动测试等通过它上电/断电, 与具体的继电器硬件无关
+pub trait PowerSwitch: Send {
+    /// 打开全部通道
+    fn turn_on(&mut self) -> Result<()>;
+
+    /// 关闭全部通道
+    fn turn_off(&mut self) -> Result<()>;
+
+    /// 单独控制一路通道, 多工位测试时每个工位使用各自的通道
+    fn set_channel(&mut self, channel: u8, on: bool) -> Result<()>;
+
+    /// 读取全部通道的状态
+    fn get_channels(&mut self) -> Result<Vec<bool>>;
+
+    /// 连接后的自检, 默认开关一次全部通道确认硬件应答
+    fn self_test(&mut self) -> Result<()> {
+        self.turn_on()?;
+        std::thread::sleep(Duration::from_millis(500));
+        self.turn_off()
+    }
+
+    /// 需要操作员确认的切换返回等待对象, 由调用者释放 RELAY 后等待
+    fn take_operator_wait(&mut self) -> Option<OperatorWait> {
+        None
+    }
+
+    /// 是否由操作员手动切换电源
+    fn is_manual(&self) -> bool {
+        false
+    }
+}
+
+pub static RELAY: Lazy<Mutex<Box<dyn PowerSwitch>>> =
+    Lazy::new(|| Mutex::new(Box::new(Relay::new())));
+
+/// 通过 RELAY 切换电源, 手动切换时在释放 RELAY 之后等待操作员确认, 不阻塞其他工位
+pub fn switch_power(op: impl FnOnce(&mut dyn PowerSwitch) -> Result<()>) -> Result<()> {
+    let wait = {
+        let mut relay = RELAY.lock().unwrap();
+        op(relay.as_mut())?;
+        relay.take_operator_wait()
+    };
+
+    match wait {
+        Some(wait) => wait.wait(),
+        None => Ok(()),
+    }
+}
+
+/// 供电开关的硬件类型, 由前端在连接继电器时选择
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PowerSwitchConfig {
+    ModbusRtu {
+        slave: Option<u8>,
+        coil_count: Option<u16>,
+    },
+    AsciiUsb {
+        channel_count: Option<u8>,
+    },
+    ModbusTcp {
+        host: String,
+        port: Option<u16>,
+        unit_id: Option<u8>,
+        coil_count: Option<u16>,
+    },
+    Manual {
+        channel_count: Option<u8>,
+        timeout_s: Option<u64>,
+    },
+}
+
+impl Default for PowerSwitchConfig {
+    fn default() -> Self {
+        PowerSwitchConfig::ModbusRtu {
+            slave: None,
+            coil_count: None,
+        }
+    }
+}
+
+/// 按配置创建供电开关, 串口类的硬件使用 sp/baud 打开串口
+pub fn open_power_switch(
+    sp: &str,
+    baud: u32,
+    config: &PowerSwitchConfig,
+) -> Result<Box<dyn PowerSwitch>> {
+    let open_port = || {
+        serialport::new(sp, baud)
+            .timeout(Duration::from_millis(50))
+            .open()
+    };
+
+    let switch: Box<dyn PowerSwitch> = match config {
+        PowerSwitchConfig::ModbusRtu { slave, coil_count } => Box::new(Relay {
+            port: Some(open_port()?),
+            slave: slave.unwrap_or(DEFAULT_RELAY_SLAVE),
+            coil_count: coil_count.unwrap_or(DEFAULT_RELAY_COIL_COUNT),
+        }),
+        PowerSwitchConfig::AsciiUsb { channel_count } => Box::new(AsciiUsbRelay::new(
+            open_port()?,
+            channel_count.unwrap_or(DEFAULT_USB_RELAY_CHANNEL_COUNT),
+        )),
+        PowerSwitchConfig::ModbusTcp {
+            host,
+            port,
+            unit_id,
+            coil_count,
+        } => Box::new(ModbusTcpRelay::connect(
+            host,
+            port.unwrap_or(502),
+            unit_id.unwrap_or(DEFAULT_RELAY_SLAVE),
+            coil_count.unwrap_or(DEFAULT_RELAY_COIL_COUNT),
+        )?),
+        PowerSwitchConfig::Manual {
+            channel_count,
+            timeout_s,
+        } => Box::new(ManualPowerSwitch::new(
+            channel_count.unwrap_or(1),
+            timeout_s.unwrap_or(60),
+        )),
+    };
+
+    Ok(switch)
+}
+
+/// 手动上电/断电提示
+#[derive(Debug, Serialize, Clone)]
+pub struct PowerSwitchPrompt {
+    pub id: u32,             // 确认时原样返回, 多个工位同时等待时区分各自的提示
+    pub channel: Option<u8>, // None 表示全部通道
+    pub on: bool,
+    pub timeout_s: u64,
+}
+
+/// 等待确认的提示, 值为操作员的确认结果
+static OPERATOR_CONFIRM: Lazy<(Mutex<HashMap<u32, Option<bool>>>, Condvar)> =
+    Lazy::new(|| (Mutex::new(HashMap::new()), Condvar::new()));
+
+static NEXT_PROMPT_ID: AtomicU32 = AtomicU32::new(1);
+
+/// 前端确认(或拒绝)手动切换电源, 已超时的提示忽略
+pub fn confirm_power_switch(id: u32, confirmed: bool) {
+    let (lock, cvar) = &*OPERATOR_CONFIRM;
+    if let Some(pending) = lock.lock().unwrap().get_mut(&id) {
+        *pending = Some(confirmed);
+    }
+    cvar.notify_all();
+}
+
+/// 等待操作员确认一次手动切换, 确认后更新通道状态
+pub struct OperatorWait {
+    id: u32,
+    timeout_s: u64,
+    channels: Arc<Mutex<Vec<bool>>>,
+    channel: Option<u8>,
+    on: bool,
+}
+
+impl OperatorWait {
+    pub fn wait(self) -> Result<()> {
+        let (lock, cvar) = &*OPERATOR_CONFIRM;
+        let confirmed = {
+            let (mut pending, _) = cvar
+                .wait_timeout_while(
+                    lock.lock().unwrap(),
+                    Duration::from_secs(self.timeout_s),
+                    |pending| pending.get(&self.id) == Some(&None),
+                )
+                .unwrap();
+            pending.remove(&self.id).flatten()
+        };
+
+        match confirmed {
+            Some(true) => {
+                let mut channels = self.channels.lock().unwrap();
+                match self.channel {
+                    Some(channel) => channels[channel as usize] = self.on,
+                    None => channels.iter_mut().for_each(|c| *c = self.on),
+                }
+                Ok(())
+            }
+            Some(false) => bail!("operator cancelled power switch"),
+            None => bail!("operator did not confirm power switch"),
+        }
+    }
+}
+
+/// 没有继电器的工位, 提示操作员手动上电/断电, 由 switch_power 等待确认
+pub struct ManualPowerSwitch {
+    channels: Arc<Mutex<Vec<bool>>>,
+    timeout_s: u64,
+    pending: Option<OperatorWait>,
+}
+
+impl ManualPowerSwitch {
+    pub fn new(channel_count: u8, timeout_s: u64) -> Self {
+        ManualPowerSwitch {
+            channels: Arc::new(Mutex::new(vec![false; channel_count as usize])),
+            timeout_s,
+            pending: None,
+        }
+    }
+
+    fn prompt(&mut self, channel: Option<u8>, on: bool) {
+        let id = NEXT_PROMPT_ID.fetch_add(1, Ordering::SeqCst);
+        OPERATOR_CONFIRM.0.lock().unwrap().insert(id, None);
+
+        LOGGER.lock().unwrap().warning(
+            format!(
+                "请手动{}电源{}",
+                if on { "打开" } else { "关闭" },
+                channel
+                    .map(|c| format!(" (通道 {})", c))
+                    .unwrap_or_default()
+            )
+            .as_str(),
+        );
+        LOGGER.lock().unwrap().emit(
+            "power_switch_prompt",
+            PowerSwitchPrompt {
+                id,
+                channel,
+                on,
+                timeout_s: self.timeout_s,
+            },
+        );
+
+        self.pending = Some(OperatorWait {
+            id,
+            timeout_s: self.timeout_s,
+            channels: Arc::clone(&self.channels),
+            channel,
+            on,
+        });
+    }
+}
+
+impl PowerSwitch for ManualPowerSwitch {
+    fn turn_on(&mut self) -> Result<()> {
+        self.prompt(None, true);
+        Ok(())
+    }
+
+    fn turn_off(&mut self) -> Result<()> {
+        self.prompt(None, false);
+        Ok(())
+    }
+
+    fn set_channel(&mut self, channel: u8, on: bool) -> Result<()> {
+        if channel as usize >= self.channels.lock().unwrap().len() {
+            bail!("relay channel {} out of range", channel);
+        }
+
+        self.prompt(Some(channel), on);
+        Ok(())
+    }
+
+    /// 无法读回硬件状态, 返回操作员确认过的状态
+    fn get_channels(&mut self) -> Result<Vec<bool>> {
+        Ok(self.channels.lock().unwrap().clone())
+    }
+
+    /// 连接时不需要操作员开关一次
+    fn self_test(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn take_operator_wait(&mut self) -> Option<OperatorWait> {
+        self.pending.take()
+    }
+
+    fn is_manual(&self) -> bool {
+        true
+    }
+}
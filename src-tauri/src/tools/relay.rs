@@ -1,65 +1,153 @@
+use crate::tools::*;
 use anyhow::{bail, Ok, Result};
-use once_cell::sync::Lazy;
 use serialport::{self, SerialPort};
-use std::sync::Mutex;
-use std::{thread, time::Duration};
 
+pub const DEFAULT_RELAY_SLAVE: u8 = 0x01;
+pub const DEFAULT_RELAY_COIL_COUNT: u16 = 4;
+
+/// Modbus RTU 继电器板, 每个线圈对应一路继电器
 pub struct Relay {
     pub port: Option<Box<dyn SerialPort>>,
+    pub slave: u8,
+    pub coil_count: u16,
 }
 
-pub static RELAY: Lazy<Mutex<Relay>> = Lazy::new(|| Mutex::new(Relay::new()));
-
 impl Relay {
     pub fn new() -> Self {
-        Relay { port: None }
+        Relay {
+            port: None,
+            slave: DEFAULT_RELAY_SLAVE,
+            coil_count: DEFAULT_RELAY_COIL_COUNT,
+        }
     }
 
-    pub fn turn_on(&mut self) -> Result<()> {
-        let mut cmds: Vec<[u8; 8]> = Vec::new();
-        cmds.push([0x01, 0x05, 0x00, 0x00, 0xFF, 0x00, 0x8C, 0x3A]);
-        cmds.push([0x01, 0x05, 0x00, 0x01, 0xFF, 0x00, 0xDD, 0xFA]);
-        cmds.push([0x01, 0x05, 0x00, 0x02, 0xFF, 0x00, 0x2D, 0xFA]);
-        cmds.push([0x01, 0x05, 0x00, 0x03, 0xFF, 0x00, 0x7C, 0x3A]);
-
-        if let Some(ref mut port) = self.port {
-            for cmd in cmds {
-                thread::sleep(Duration::from_millis(30));
-                match port.write(&cmd) {
-                    core::result::Result::Ok(_) => {
-                        // return Ok(());
-                    }
-                    Err(e) => {
-                        bail!(e);
-                    }
-                }
-            }
+    fn bus(&mut self) -> Result<ModbusRtu<'_>> {
+        let slave = self.slave;
+        match self.port {
+            Some(ref mut port) => Ok(ModbusRtu {
+                port: port.as_mut(),
+                slave,
+            }),
+            None => bail!("relay port is not connected"),
         }
+    }
+}
 
-        Ok(())
+impl PowerSwitch for Relay {
+    fn turn_on(&mut self) -> Result<()> {
+        let count = self.coil_count;
+        coils_set_all(&mut self.bus()?, count, true)
     }
 
-    pub fn turn_off(&mut self) -> Result<()> {
-        let mut cmds: Vec<[u8; 8]> = Vec::new();
-        cmds.push([0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0xCD, 0xCA]);
-        cmds.push([0x01, 0x05, 0x00, 0x01, 0x00, 0x00, 0x9C, 0x0A]);
-        cmds.push([0x01, 0x05, 0x00, 0x02, 0x00, 0x00, 0x6C, 0x0A]);
-        cmds.push([0x01, 0x05, 0x00, 0x03, 0x00, 0x00, 0x3D, 0xCA]);
-
-        if let Some(ref mut port) = self.port {
-            for cmd in cmds {
-                thread::sleep(Duration::from_millis(30));
-                match port.write(&cmd) {
-                    core::result::Result::Ok(_) => {
-                        // return Ok(());
-                    }
-                    Err(e) => {
-                        bail!(e);
-                    }
-                }
-            }
+    fn turn_off(&mut self) -> Result<()> {
+        let count = self.coil_count;
+        coils_set_all(&mut self.bus()?, count, false)
+    }
+
+    fn set_channel(&mut self, channel: u8, on: bool) -> Result<()> {
+        let count = self.coil_count;
+        coils_set_channel(&mut self.bus()?, count, channel, on)
+    }
+
+    /// 继电器未连接时返回空列表
+    fn get_channels(&mut self) -> Result<Vec<bool>> {
+        if self.port.is_none() {
+            return Ok(Vec::new());
         }
 
-        Ok(())
+        let count = self.coil_count;
+        modbus_read_coils(&mut self.bus()?, 0, count)
+    }
+}
+
+/// Modbus TCP 继电器模块, 线圈定义与 RTU 继电器板相同
+pub struct ModbusTcpRelay {
+    bus: ModbusTcp,
+    coil_count: u16,
+}
+
+impl ModbusTcpRelay {
+    pub fn connect(host: &str, port: u16, unit_id: u8, coil_count: u16) -> Result<Self> {
+        Ok(ModbusTcpRelay {
+            bus: ModbusTcp::connect(host, port, unit_id)?,
+            coil_count,
+        })
+    }
+}
+
+impl PowerSwitch for ModbusTcpRelay {
+    fn turn_on(&mut self) -> Result<()> {
+        coils_set_all(&mut self.bus, self.coil_count, true)
+    }
+
+    fn turn_off(&mut self) -> Result<()> {
+        coils_set_all(&mut self.bus, self.coil_count, false)
+    }
+
+    fn set_channel(&mut self, channel: u8, on: bool) -> Result<()> {
+        coils_set_channel(&mut self.bus, self.coil_count, channel, on)
+    }
+
+    fn get_channels(&mut self) -> Result<Vec<bool>> {
+        modbus_read_coils(&mut self.bus, 0, self.coil_count)
+    }
+}
+
+/// 单独控制一路继电器, 多工位测试时每个工位使用各自的通道
+fn coils_set_channel(
+    bus: &mut dyn ModbusTransport,
+    coil_count: u16,
+    channel: u8,
+    on: bool,
+) -> Result<()> {
+    if channel as u16 >= coil_count {
+        bail!("relay channel {} out of range", channel);
+    }
+
+    modbus_write_single_coil(bus, channel as u16, on)?;
+
+    let channels = modbus_read_coils(bus, 0, coil_count)?;
+    verify_channels(&channels, |i| i == channel as usize, on)
+}
+
+fn coils_set_all(bus: &mut dyn ModbusTransport, coil_count: u16, on: bool) -> Result<()> {
+    let values = vec![on; coil_count as usize];
+    modbus_write_multiple_coils(bus, 0, &values)?;
+
+    let channels = modbus_read_coils(bus, 0, coil_count)?;
+    verify_channels(&channels, |_| true, on)
+}
+
+/// 检查读回的通道状态, 确认继电器已切换; 继电器板未上电或串口接错时读回失败
+pub fn verify_channels(
+    channels: &[bool],
+    selected: impl Fn(usize) -> bool,
+    on: bool,
+) -> Result<()> {
+    for (i, state) in channels.iter().enumerate() {
+        if selected(i) && *state != on {
+            bail!(
+                "relay channel {} did not switch {}",
+                i,
+                if on { "on" } else { "off" }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_channels_checks_selected_only() {
+        let channels = [true, false, true];
+
+        assert!(verify_channels(&channels, |i| i != 1, true).is_ok());
+        assert!(verify_channels(&channels, |i| i == 1, false).is_ok());
+        assert!(verify_channels(&channels, |_| true, true).is_err());
+        assert!(verify_channels(&channels, |i| i == 2, false).is_err());
     }
 }
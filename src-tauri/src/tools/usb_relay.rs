@@ -0,0 +1,108 @@
+use crate::tools::*;
+use anyhow::{bail, Ok, Result};
+use serialport::{ClearBuffer, SerialPort};
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_USB_RELAY_CHANNEL_COUNT: u8 = 4;
+
+/// 等待命令提示符的超时时间
+const PROMPT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// ASCII 命令的 USB 继电器(虚拟串口), 命令格式为 `relay on 0`、`relay off 0`、`relay read 0`,
+/// 模块回显命令, 执行完成后输出 `>` 提示符
+pub struct AsciiUsbRelay {
+    port: Box<dyn SerialPort>,
+    channel_count: u8,
+}
+
+impl AsciiUsbRelay {
+    pub fn new(port: Box<dyn SerialPort>, channel_count: u8) -> Self {
+        AsciiUsbRelay {
+            port,
+            channel_count,
+        }
+    }
+
+    /// 发送一条命令, 返回去掉回显和提示符后的应答
+    fn command(&mut self, cmd: &str) -> Result<String> {
+        self.port.clear(ClearBuffer::Input)?;
+        self.port.write_all(format!("{}\r", cmd).as_bytes())?;
+        self.port.flush()?;
+
+        let deadline = Instant::now() + PROMPT_TIMEOUT;
+        let mut resp = Vec::new();
+        let mut buf = [0u8; 64];
+        while !resp.ends_with(b">") {
+            match self.port.read(&mut buf) {
+                core::result::Result::Ok(n) => resp.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(e) => bail!(e),
+            }
+
+            if Instant::now() > deadline {
+                bail!("usb relay response timeout");
+            }
+        }
+
+        let resp = String::from_utf8_lossy(&resp[..resp.len() - 1]).to_string();
+        Ok(resp
+            .trim()
+            .strip_prefix(cmd)
+            .unwrap_or(resp.trim())
+            .trim()
+            .to_string())
+    }
+
+    fn read_channel(&mut self, channel: u8) -> Result<bool> {
+        let resp = self.command(format!("relay read {}", channel).as_str())?;
+        match resp.as_str() {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => bail!("unexpected usb relay response: {}", resp),
+        }
+    }
+
+    fn write_channel(&mut self, channel: u8, on: bool) -> Result<()> {
+        let action = if on { "on" } else { "off" };
+        self.command(format!("relay {} {}", action, channel).as_str())?;
+        Ok(())
+    }
+}
+
+impl PowerSwitch for AsciiUsbRelay {
+    fn turn_on(&mut self) -> Result<()> {
+        for channel in 0..self.channel_count {
+            self.write_channel(channel, true)?;
+        }
+
+        let channels = self.get_channels()?;
+        verify_channels(&channels, |_| true, true)
+    }
+
+    fn turn_off(&mut self) -> Result<()> {
+        for channel in 0..self.channel_count {
+            self.write_channel(channel, false)?;
+        }
+
+        let channels = self.get_channels()?;
+        verify_channels(&channels, |_| true, false)
+    }
+
+    fn set_channel(&mut self, channel: u8, on: bool) -> Result<()> {
+        if channel >= self.channel_count {
+            bail!("relay channel {} out of range", channel);
+        }
+
+        self.write_channel(channel, on)?;
+
+        let channels = self.get_channels()?;
+        verify_channels(&channels, |i| i == channel as usize, on)
+    }
+
+    fn get_channels(&mut self) -> Result<Vec<bool>> {
+        (0..self.channel_count)
+            .map(|channel| self.read_channel(channel))
+            .collect()
+    }
+}
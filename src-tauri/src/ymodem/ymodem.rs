@@ -1,6 +1,7 @@
 use crate::ymodem::xymodem_util::*;
-use log::warn;
+use log::{debug, warn};
 use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 // TODO: Send CAN byte after too many errors
 // TODO: Handle CAN bytes while sending
@@ -15,14 +16,37 @@ pub const NAK: u8 = 0x15;
 pub const CAN: u8 = 0x18;
 pub const CRC: u8 = 0x43;
 
+/// Sent to abort a transfer. Receivers abort after two consecutive CAN bytes,
+/// a few extra are sent in case one of them is lost.
+const CANCEL_SEQUENCE: [u8; 5] = [CAN; 5];
+
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Progress reported by [`Ymodem::send`].
+#[derive(Copy, Clone, Debug)]
+pub enum SendEvent {
+    /// Waiting for the receiver to request the transfer and acknowledge the start frame.
+    Handshake,
+    /// A data block was acknowledged.
+    Block {
+        block: u32,
+        total_blocks: u32,
+        bytes_sent: u64,
+        /// Total number of block resends so far in this transfer.
+        retries: u32,
+    },
+    /// All blocks were sent, sending EOT and the end frame.
+    Finalizing,
+}
+
 /// Configuration for the YMODEM transfer.
 #[allow(dead_code)]
 #[derive(Copy, Clone, Debug)]
 pub struct Ymodem {
     /// The number of errors that can occur before the communication is
     /// considered a failure. Errors include unexpected bytes and timeouts waiting for bytes.
+    ///
+    /// While sending data blocks this applies to each block separately.
     pub max_errors: u32,
 
     /// The number of errors that can occur before the communication is
@@ -270,6 +294,9 @@ impl Ymodem {
     ///
     /// `dev` should be the serial communication channel (e.g. the serial device).
     /// `stream` should be the message to send (e.g. a file).
+    /// `on_event` is called when the transfer enters a new phase and after each acknowledged block.
+    /// Setting `cancel` aborts the transfer: CAN bytes are sent to the receiver and
+    /// `Error::Canceled` is returned.
     ///
     /// # Timeouts
     /// This method has no way of setting the timeout of `dev`, so it's up to the caller
@@ -282,26 +309,50 @@ impl Ymodem {
         stream: &mut R,
         file_name: String,
         file_size_in_bytes: u64,
+        on_event: &mut dyn FnMut(SendEvent),
+        cancel: &AtomicBool,
     ) -> Result<()> {
         self.errors = 0;
         let packets_to_send = f64::ceil(file_size_in_bytes as f64 / 1024.0) as u32;
         let last_packet_size = file_size_in_bytes % 1024;
 
         dbg!("Starting YMODEM transfer");
-        (self.start_send(dev))?;
+        on_event(SendEvent::Handshake);
+        (self.start_send(dev, cancel))?;
         dbg!("First byte received. Sending start frame.");
         (self.send_start_frame(dev, file_name, file_size_in_bytes))?;
         dbg!("Start frame acknowledged. Sending stream.");
-        (self.send_stream(dev, stream, packets_to_send, last_packet_size))?;
+        (self.send_stream(
+            dev,
+            stream,
+            packets_to_send,
+            last_packet_size,
+            on_event,
+            cancel,
+        ))?;
         dbg!("Sending EOT");
-        (self.finish_send(dev))?;
+        on_event(SendEvent::Finalizing);
+        (self.finish_send(dev, cancel))?;
+
+        Ok(())
+    }
+
+    /// Sends CAN bytes and returns `Error::Canceled` if the transfer was canceled by the caller.
+    fn check_cancel<D: Write>(&self, dev: &mut D, cancel: &AtomicBool) -> Result<()> {
+        if cancel.load(Ordering::SeqCst) {
+            warn!("Transfer canceled, sending CAN bytes");
+            (dev.write_all(&CANCEL_SEQUENCE))?;
+            (dev.flush())?;
+            return Err(Error::Canceled);
+        }
 
         Ok(())
     }
 
-    fn start_send<D: Read + Write>(&mut self, dev: &mut D) -> Result<()> {
+    fn start_send<D: Read + Write>(&mut self, dev: &mut D, cancel: &AtomicBool) -> Result<()> {
         let mut cancels = 0u32;
         loop {
+            (self.check_cancel(dev, cancel))?;
             match (get_byte_timeout(dev))? {
                 Some(c) => match c {
                     CRC => {
@@ -428,8 +479,12 @@ impl Ymodem {
         stream: &mut R,
         packets_to_send: u32,
         last_packet_size: u64,
+        on_event: &mut dyn FnMut(SendEvent),
+        cancel: &AtomicBool,
     ) -> Result<()> {
         let mut block_num = 0u32;
+        let mut bytes_sent = 0u64;
+        let mut retries = 0u32;
         loop {
             let packet_size = if block_num + 1 == packets_to_send && last_packet_size <= 128 {
                 128
@@ -456,35 +511,53 @@ impl Ymodem {
             buff.push(((crc >> 8) & 0xFF) as u8);
             buff.push((crc & 0xFF) as u8);
 
-            println!("Sending block {}", block_num);
-            (dev.write_all(&buff))?;
-
-            match (get_byte_timeout(dev))? {
-                Some(c) => {
-                    if c == ACK {
-                        dbg!("Received ACK for block {}", block_num);
-                        continue;
-                    } else {
-                        warn!("Expected ACK, got {}", c);
+            // A block the receiver did not acknowledge is sent again: moving on to the next
+            // block would leave a gap in the received file. Each block gets its own
+            // `max_errors` budget, so occasional errors spread over a large file don't fail
+            // the transfer.
+            let mut block_errors = 0u32;
+            loop {
+                (self.check_cancel(dev, cancel))?;
+                debug!("Sending block {}", block_num);
+                (dev.write_all(&buff))?;
+
+                match (get_byte_timeout(dev))? {
+                    Some(c) => {
+                        if c == ACK {
+                            debug!("Received ACK for block {}", block_num);
+                            break;
+                        } else {
+                            warn!("Expected ACK, got {}", c);
+                        }
+                        // TODO handle CAN bytes
                     }
-                    // TODO handle CAN bytes
+                    None => warn!("Timeout waiting for ACK for block {}", block_num),
                 }
-                None => warn!("Timeout waiting for ACK for block {}", block_num),
-            }
 
-            self.errors += 1;
+                block_errors += 1;
+                retries += 1;
 
-            if self.errors >= self.max_errors {
-                eprint!(
-                    "Exhausted max retries ({}) while sending block {} in YMODEM transfer",
-                    self.max_errors, block_num
-                );
-                return Err(Error::ExhaustedRetries);
+                if block_errors >= self.max_errors {
+                    eprint!(
+                        "Exhausted max retries ({}) while sending block {} in YMODEM transfer",
+                        self.max_errors, block_num
+                    );
+                    return Err(Error::ExhaustedRetries);
+                }
             }
+
+            bytes_sent += n as u64;
+            on_event(SendEvent::Block {
+                block: block_num,
+                total_blocks: packets_to_send,
+                bytes_sent,
+                retries,
+            });
         }
     }
 
-    fn finish_send<D: Read + Write>(&mut self, dev: &mut D) -> Result<()> {
+    fn finish_send<D: Read + Write>(&mut self, dev: &mut D, cancel: &AtomicBool) -> Result<()> {
+        (self.check_cancel(dev, cancel))?;
         (dev.write_all(&[EOT]))?;
 
         match (get_byte_timeout(dev))? {
@@ -505,7 +578,10 @@ impl Ymodem {
             None => warn!("Timeout waiting for stream"),
         }
 
-        std::thread::sleep(std::time::Duration::from_millis(3000));
+        for _ in 0..60 {
+            (self.check_cancel(dev, cancel))?;
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
 
         self.send_end_frame(dev)?;
 
@@ -556,3 +632,114 @@ impl Ymodem {
         return Ok(());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::io;
+
+    /// Records every write and answers reads from a queue of reply bytes,
+    /// timing out once the queue is empty.
+    struct MockDevice {
+        writes: Vec<Vec<u8>>,
+        replies: VecDeque<u8>,
+    }
+
+    impl MockDevice {
+        fn new(replies: &[u8]) -> Self {
+            MockDevice {
+                writes: Vec::new(),
+                replies: replies.iter().copied().collect(),
+            }
+        }
+    }
+
+    impl Read for MockDevice {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.replies.pop_front() {
+                Some(b) => {
+                    buf[0] = b;
+                    Ok(1)
+                }
+                None => Err(io::Error::new(io::ErrorKind::TimedOut, "no reply")),
+            }
+        }
+    }
+
+    impl Write for MockDevice {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn send_blocks(
+        ymodem: &mut Ymodem,
+        dev: &mut MockDevice,
+        data: &[u8],
+        events: &mut Vec<(u32, u64, u32)>,
+    ) -> Result<()> {
+        let packets = data.len().div_ceil(1024) as u32;
+        let mut on_event = |event| {
+            if let SendEvent::Block {
+                block,
+                bytes_sent,
+                retries,
+                ..
+            } = event
+            {
+                events.push((block, bytes_sent, retries));
+            }
+        };
+
+        ymodem.send_stream(
+            dev,
+            &mut &data[..],
+            packets,
+            data.len() as u64 % 1024,
+            &mut on_event,
+            &AtomicBool::new(false),
+        )
+    }
+
+    #[test]
+    fn resends_block_after_nak() {
+        let data = vec![0x5a; 1124];
+        let mut dev = MockDevice::new(&[NAK, ACK, ACK]);
+        let mut events = Vec::new();
+
+        send_blocks(&mut Ymodem::new(), &mut dev, &data, &mut events).unwrap();
+
+        assert_eq!(dev.writes.len(), 3);
+        assert_eq!(dev.writes[0], dev.writes[1]);
+        assert_eq!(&dev.writes[1][..3], &[STX, 1, 0xFE]);
+        assert_eq!(&dev.writes[2][..3], &[SOH, 2, 0xFD]);
+        assert_eq!(events, vec![(1, 1024, 1), (2, 1124, 1)]);
+    }
+
+    #[test]
+    fn error_budget_is_per_block() {
+        let data = vec![0x5a; 1124];
+        let mut ymodem = Ymodem::new();
+        ymodem.max_errors = 2;
+
+        // one error on each block stays within the budget
+        let mut dev = MockDevice::new(&[NAK, ACK, NAK, ACK]);
+        let mut events = Vec::new();
+        send_blocks(&mut ymodem, &mut dev, &data, &mut events).unwrap();
+        assert_eq!(events, vec![(1, 1024, 1), (2, 1124, 2)]);
+
+        // two errors on the same block exhaust it
+        let mut dev = MockDevice::new(&[ACK, NAK]);
+        let mut events = Vec::new();
+        let result = send_blocks(&mut ymodem, &mut dev, &data, &mut events);
+        assert!(matches!(result, Err(Error::ExhaustedRetries)));
+        assert_eq!(dev.writes.len(), 3);
+        assert_eq!(events, vec![(1, 1024, 0)]);
+    }
+}
@@ -3,6 +3,7 @@ export default {
     motorCtrl: 'Control',
     spdMonitor: 'Monitor',
     startupTest: 'StartStop',
+    station: 'Station',
     setting: 'Settings',
   },
   main: {
@@ -72,11 +73,39 @@ export default {
     relayDisconnect: 'Disconnect',
     relayStart: 'Start',
     relayStop: 'Stop',
+    relayChannels: 'Channels',
+    relayBackend: 'Type',
+    relayHost: 'Host',
+    relayKinds: {
+      modbus_rtu: 'Modbus RTU',
+      ascii_usb: 'USB relay',
+      modbus_tcp: 'Modbus TCP',
+      manual: 'Manual',
+    },
 
     status: 'Status',
     SuccessCnt: 'Success Cnt',
     FailedCnt: 'Failed Cnt',
     testDuration: 'Duration',
+    abortReason: 'Abort Reason',
+    powerSwitchTitle: 'Manual Power',
+    powerOnPrompt: 'Please switch the motor power ON{channel}, then confirm.',
+    powerOffPrompt: 'Please switch the motor power OFF{channel}, then confirm.',
+    powerChannel: ' (channel {channel})',
+  },
+  station: {
+    title: 'Production Station',
+    serialNumber: 'Serial No.',
+    operator: 'Operator',
+    plan: 'Test Plan',
+    historyDir: 'History Dir',
+    result: 'Result',
+    testing: 'Testing',
+    fwVersion: 'Firmware Version',
+    metric: 'Item',
+    value: 'Value',
+    history: 'History',
+    stage: 'Stage',
   },
   settings: {
     title: 'Settings',
@@ -103,4 +132,14 @@ export default {
   download: 'Download',
   downloading: 'Downloading',
   selectFile: 'Select File',
+  upgradePhase: {
+    idle: 'Idle',
+    entering_iap: 'Entering IAP ({attempts})',
+    handshake: 'Handshake',
+    transferring: 'Block {block} / {total}, retries {retries}',
+    finalizing: 'Finalizing',
+    done: 'Done',
+    failed: 'Failed',
+    cancelled: 'Cancelled',
+  },
 }
\ No newline at end of file
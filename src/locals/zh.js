@@ -3,6 +3,7 @@ export default {
     motorCtrl: '电机控制',
     spdMonitor: '转速监控',
     startupTest: '启停测试',
+    station: '产线测试',
     setting: '设置',
   },
   main: {
@@ -72,11 +73,39 @@ export default {
     relayDisconnect: '断开',
     relayStart: '上电',
     relayStop: '断电',
+    relayChannels: '通道',
+    relayBackend: '类型',
+    relayHost: '地址',
+    relayKinds: {
+      modbus_rtu: 'Modbus RTU',
+      ascii_usb: 'USB 继电器',
+      modbus_tcp: 'Modbus TCP',
+      manual: '手动',
+    },
 
     status: '状态',
     SuccessCnt: '成功次数',
     FailedCnt: '失败次数',
     testDuration: '测试时长',
+    abortReason: '中止原因',
+    powerSwitchTitle: '手动供电',
+    powerOnPrompt: '请手动打开电机电源{channel}, 完成后确认。',
+    powerOffPrompt: '请手动关闭电机电源{channel}, 完成后确认。',
+    powerChannel: ' (通道 {channel})',
+  },
+  station: {
+    title: '产线测试',
+    serialNumber: '序列号',
+    operator: '操作员',
+    plan: '测试计划',
+    historyDir: '记录目录',
+    result: '测试结果',
+    testing: '测试中',
+    fwVersion: '固件版本',
+    metric: '测试项',
+    value: '测量值',
+    history: '历史记录',
+    stage: '阶段',
   },
   settings: {
     title: '设置',
@@ -103,6 +132,16 @@ export default {
   download: '下载',
   downloading: '下载中',
   selectFile: '选择文件',
+  upgradePhase: {
+    idle: '空闲',
+    entering_iap: '进入 IAP 模式 ({attempts})',
+    handshake: '握手',
+    transferring: '发送数据块 {block} / {total}, 重试 {retries}',
+    finalizing: '结束传输',
+    done: '完成',
+    failed: '失败',
+    cancelled: '已取消',
+  },
 
 
 }
\ No newline at end of file
@@ -3,6 +3,7 @@ import { createRouter, createWebHashHistory } from "vue-router";
 import Motor_main from '../views/Motor_main.vue'
 import speedMonitor from '../views/Speed_monitor.vue'
 import stressTest from '../views/Stress_test.vue'
+import station from '../views/Station.vue'
 import setting from '../views/Setting.vue'
 
 
@@ -41,6 +42,17 @@ const routes = [
     },
     component: stressTest
   },
+  {
+    path: '/station',
+    name: 'station',
+    meta: {
+      //	当前页面要不要缓存
+      keepAlive: true,
+      //	当前页面层级
+      deepth: 5,
+    },
+    component: station
+  },
   {
     path: '/setting',
     name: 'setting',
@@ -67,8 +67,9 @@ export const MotorParams = defineStore('params', () => {
 export const useRelayStore = defineStore('relay', () => {
   const isConnected = ref(false);
   const isPowerOn = ref(false);
+  const channels = ref([]);
 
-  return { isConnected, isPowerOn };
+  return { isConnected, isPowerOn, channels };
 })
 
 // export const useMotorStore = defineStore('motor', {
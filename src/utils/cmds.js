@@ -41,9 +41,9 @@ function formatSeconds(sec) {
   return `${hours.toString().padStart(2, '0')}:${minutes.toString().padStart(2, '0')}:${seconds.toString().padStart(2, '0')}`;
 }
 
-function cmd_connect_motor(sp, baud) {
+function cmd_connect_motor(sp, baud, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('init_serial_port', { sp: sp, baud: baud })
+    invoke('init_serial_port', { sp: sp, baud: baud, deviceId: deviceId })
       .then((data) => {
         resolve(data)
       })
@@ -54,9 +54,9 @@ function cmd_connect_motor(sp, baud) {
   })
 }
 
-function cmd_disconnect_motor(sp, baud) {
+function cmd_disconnect_motor(sp, baud, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('deinit_serial_port', {})
+    invoke('deinit_serial_port', { deviceId: deviceId })
       .then((data) => {
         resolve(data)
       })
@@ -67,9 +67,9 @@ function cmd_disconnect_motor(sp, baud) {
   })
 }
 
-function cmd_get_motor_current_rps() {
+function cmd_get_motor_current_rps(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_motor_current_rps', {})
+    invoke('get_motor_current_rps', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -84,9 +84,9 @@ function cmd_get_motor_current_rps() {
   });
 }
 
-function cmd_get_motor_current_pos() {
+function cmd_get_motor_current_pos(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_motor_current_pos', {})
+    invoke('get_motor_current_pos', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -101,9 +101,9 @@ function cmd_get_motor_current_pos() {
   });
 }
 
-function cmd_get_motor_params() {
+function cmd_get_motor_params(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_motor_params', {})
+    invoke('get_motor_params', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -115,9 +115,9 @@ function cmd_get_motor_params() {
   })
 }
 
-function cmd_get_motor_static_params() {
+function cmd_get_motor_static_params(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_motor_static_params', {})
+    invoke('get_motor_static_params', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -129,9 +129,9 @@ function cmd_get_motor_static_params() {
   })
 }
 
-function cmd_get_motor_status() {
+function cmd_get_motor_status(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_motor_status', {})
+    invoke('get_motor_status', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -143,9 +143,9 @@ function cmd_get_motor_status() {
   })
 }
 
-function cmd_get_motor_special_params() {
+function cmd_get_motor_special_params(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_motor_special_params', {})
+    invoke('get_motor_special_params', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -171,9 +171,9 @@ function cmd_get_avaliable_ports() {
   })
 }
 
-function cmd_update_motor_rps(rps) {
+function cmd_update_motor_rps(rps, ramp = null, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_rps', { rps: rps })
+    invoke('update_motor_rps', { rps: rps, ramp: ramp, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -185,9 +185,9 @@ function cmd_update_motor_rps(rps) {
   })
 }
 
-function cmd_enable_motor_identify(enable) {
+function cmd_enable_motor_identify(enable, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('enable_motor_identify', { en: enable })
+    invoke('enable_motor_identify', { en: enable, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -199,9 +199,9 @@ function cmd_enable_motor_identify(enable) {
   })
 }
 
-function cmd_enable_motor_rs_online(enable) {
+function cmd_enable_motor_rs_online(enable, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('enable_motor_rs_online', { en: enable })
+    invoke('enable_motor_rs_online', { en: enable, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -213,9 +213,9 @@ function cmd_enable_motor_rs_online(enable) {
   })
 }
 
-function cmd_enable_motor_rs_recalc(enable) {
+function cmd_enable_motor_rs_recalc(enable, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('enable_motor_rs_recalc', { en: enable })
+    invoke('enable_motor_rs_recalc', { en: enable, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -227,9 +227,9 @@ function cmd_enable_motor_rs_recalc(enable) {
   })
 }
 
-function cmd_clear_motor_faults(enable) {
+function cmd_clear_motor_faults(enable, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('clear_motor_faults', {})
+    invoke('clear_motor_faults', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -241,9 +241,9 @@ function cmd_clear_motor_faults(enable) {
   })
 }
 
-function cmd_start_motor(rps) {
+function cmd_start_motor(rps, ramp = null, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('start_motor', { rps: rps })
+    invoke('start_motor', { rps: rps, ramp: ramp, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -255,9 +255,9 @@ function cmd_start_motor(rps) {
   })
 }
 
-function cmd_stop_motor() {
+function cmd_stop_motor(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('stop_motor', {})
+    invoke('stop_motor', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -269,9 +269,9 @@ function cmd_stop_motor() {
   })
 }
 
-function cmd_update_acc_max(hz) {
+function cmd_update_acc_max(hz, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_acc_max', { hz: hz })
+    invoke('update_motor_acc_max', { hz: hz, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -283,9 +283,9 @@ function cmd_update_acc_max(hz) {
   })
 }
 
-function cmd_update_acc_start(hz) {
+function cmd_update_acc_start(hz, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_acc_start', { hz: hz })
+    invoke('update_motor_acc_start', { hz: hz, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -297,9 +297,9 @@ function cmd_update_acc_start(hz) {
   })
 }
 
-function cmd_update_kp_spd(kp) {
+function cmd_update_kp_spd(kp, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_kp_spd', { kp: kp })
+    invoke('update_motor_kp_spd', { kp: kp, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -311,9 +311,9 @@ function cmd_update_kp_spd(kp) {
   })
 }
 
-function cmd_update_ki_spd(ki) {
+function cmd_update_ki_spd(ki, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_ki_spd', { ki: ki })
+    invoke('update_motor_ki_spd', { ki: ki, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -325,9 +325,9 @@ function cmd_update_ki_spd(ki) {
   })
 }
 
-function cmd_update_kp_iq(kp) {
+function cmd_update_kp_iq(kp, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_kp_iq', { kp: kp })
+    invoke('update_motor_kp_iq', { kp: kp, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -339,9 +339,9 @@ function cmd_update_kp_iq(kp) {
   })
 }
 
-function cmd_update_ki_iq(ki) {
+function cmd_update_ki_iq(ki, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_ki_ip', { ki: ki })
+    invoke('update_motor_ki_iq', { ki: ki, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -353,9 +353,9 @@ function cmd_update_ki_iq(ki) {
   })
 }
 
-function cmd_enable_motor_pos_ctrl(enable, mode) {
+function cmd_enable_motor_pos_ctrl(enable, mode, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('enable_motor_pos_ctrl', { en: enable, mode: mode })
+    invoke('enable_motor_pos_ctrl', { en: enable, mode: mode, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -367,9 +367,9 @@ function cmd_enable_motor_pos_ctrl(enable, mode) {
   })
 }
 
-function cmd_update_motor_position(pos) {
+function cmd_update_motor_position(pos, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_position', { pos: pos })
+    invoke('update_motor_position', { pos: pos, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -381,7 +381,7 @@ function cmd_update_motor_position(pos) {
   })
 }
 
-function cmd_start_record_rps(path) {
+function cmd_start_record_rps(path, deviceId = null) {
   if (path === null || path === undefined || path.trim() === '') {
     notify_failed("record path is empty!");
 
@@ -389,7 +389,7 @@ function cmd_start_record_rps(path) {
   }
 
   return new Promise(function (resolve, reject) {
-    invoke('start_record_rps', { path: path })
+    invoke('start_record_rps', { path: path, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -401,9 +401,9 @@ function cmd_start_record_rps(path) {
   })
 }
 
-function cmd_stop_record_rps() {
+function cmd_stop_record_rps(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('stop_record_rps', {})
+    invoke('stop_record_rps', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -415,9 +415,9 @@ function cmd_stop_record_rps() {
   })
 }
 
-function cmd_start_startup_test(test_param) {
+function cmd_start_startup_test(test_param, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('start_startup_task', { testParam: test_param })
+    invoke('start_startup_task', { testParam: test_param, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -429,9 +429,9 @@ function cmd_start_startup_test(test_param) {
   })
 }
 
-function cmd_stop_startup_test() {
+function cmd_stop_startup_test(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('stop_startup_task', {})
+    invoke('stop_startup_task', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -443,9 +443,9 @@ function cmd_stop_startup_test() {
   })
 }
 
-function cmd_get_startup_test_result() {
+function cmd_get_startup_test_result(deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('get_startup_test_result', {})
+    invoke('get_startup_test_result', { deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -457,9 +457,9 @@ function cmd_get_startup_test_result() {
   })
 }
 
-function cmd_update_motor_special_params(param) {
+function cmd_update_motor_special_params(param, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('update_motor_special_params', { param: param })
+    invoke('update_motor_special_params', { param: param, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -471,9 +471,9 @@ function cmd_update_motor_special_params(param) {
   })
 }
 
-function cmd_export_motor_special_params(param, path) {
+function cmd_export_motor_special_params(param, path, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('export_motor_special_params', { param: param, path: path })
+    invoke('export_motor_special_params', { param: param, path: path, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -485,9 +485,9 @@ function cmd_export_motor_special_params(param, path) {
   })
 }
 
-function cmd_import_motor_special_params(path) {
+function cmd_import_motor_special_params(path, deviceId = null) {
   return new Promise(function (resolve, reject) {
-    invoke('import_motor_special_params', { path: path })
+    invoke('import_motor_special_params', { path: path, deviceId: deviceId })
       .then((data) => {
         resolve(data);
       })
@@ -525,9 +525,9 @@ function cmd_merge_motor_fw(boot, app, output) {
   })
 }
 
-function cmd_connect_relay(sp, baud) {
+function cmd_connect_relay(sp, baud, backend = null) {
   return new Promise(function (resolve, reject) {
-    invoke('init_relay_port', { sp: sp, baud: baud })
+    invoke('init_relay_port', { sp: sp, baud: baud, backend: backend })
       .then((data) => {
         resolve(data)
       })
@@ -564,6 +564,860 @@ function cmd_set_relay_power(on) {
   })
 }
 
+function cmd_run_speed_step_test(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('run_speed_step_test', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_spd_autotune(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_spd_autotune', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_spd_autotune(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_spd_autotune', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_spd_autotune_result(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_spd_autotune_result', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_apply_spd_autotune(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('apply_spd_autotune', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_calc_current_loop_gains(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('calc_current_loop_gains', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_apply_current_loop_gains(gains, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('apply_current_loop_gains', { gains: gains, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_load_speed_profile_file(path) {
+  return new Promise(function (resolve, reject) {
+    invoke('load_speed_profile_file', { path: path })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_speed_profile(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_speed_profile', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_pause_speed_profile(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('pause_speed_profile', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_resume_speed_profile(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('resume_speed_profile', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_abort_speed_profile(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('abort_speed_profile', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_speed_profile_status(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_speed_profile_status', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_startup_test_records(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_startup_test_records', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_export_startup_test_report(path, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('export_startup_test_report', { path: path, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_startup_sweep(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_startup_sweep', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_startup_sweep(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_startup_sweep', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_startup_sweep_result(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_startup_sweep_result', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_apply_startup_sweep_best(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('apply_startup_sweep_best', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_pause_startup_task(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('pause_startup_task', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_resume_startup_task(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('resume_startup_task', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_load_startup_test_checkpoint(path) {
+  return new Promise(function (resolve, reject) {
+    invoke('load_startup_test_checkpoint', { path: path })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_resume_startup_task_from_checkpoint(path, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('resume_startup_task_from_checkpoint', { path: path, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_endurance_test(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_endurance_test', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_endurance_test(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_endurance_test', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_endurance_test_result(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_endurance_test_result', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_export_endurance_test_report(path, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('export_endurance_test_report', { path: path, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_load_test_plan_file(path) {
+  return new Promise(function (resolve, reject) {
+    invoke('load_test_plan_file', { path: path })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_test_plan(path, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_test_plan', { path: path, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_test_plan(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_test_plan', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_test_plan_result(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_test_plan_result', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_export_test_plan_report(path, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('export_test_plan_report', { path: path, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_station_test(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_station_test', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_station_test(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_station_test', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_station_status(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_station_status', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_station_history(historyDir, serialNumber) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_station_history', { historyDir: historyDir, serialNumber: serialNumber })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_list_connected_devices() {
+  return new Promise(function (resolve, reject) {
+    invoke('list_connected_devices', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_startup_stations(params) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_startup_stations', { params: params })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_startup_stations() {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_startup_stations', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_startup_station(deviceId) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_startup_station', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_pause_startup_station(deviceId) {
+  return new Promise(function (resolve, reject) {
+    invoke('pause_startup_station', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_resume_startup_station(deviceId) {
+  return new Promise(function (resolve, reject) {
+    invoke('resume_startup_station', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_startup_stations_summary() {
+  return new Promise(function (resolve, reject) {
+    invoke('get_startup_stations_summary', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_startup_station_records(deviceId) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_startup_station_records', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_export_startup_station_report(deviceId, path) {
+  return new Promise(function (resolve, reject) {
+    invoke('export_startup_station_report', { deviceId: deviceId, path: path })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_set_relay_channel(channel, on) {
+  return new Promise(function (resolve, reject) {
+    invoke('set_relay_channel', { channel: channel, on: on })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_relay_channels() {
+  return new Promise(function (resolve, reject) {
+    invoke('get_relay_channels', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_confirm_power_switch(id, confirmed) {
+  return new Promise(function (resolve, reject) {
+    invoke('confirm_power_switch', { id: id, confirmed: confirmed })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_connect_power_supply(link) {
+  return new Promise(function (resolve, reject) {
+    invoke('init_power_supply', { link: link })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_disconnect_power_supply() {
+  return new Promise(function (resolve, reject) {
+    invoke('deinit_power_supply', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_set_power_supply(voltage, currentLimit = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('set_power_supply', { voltage: voltage, currentLimit: currentLimit })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_set_power_supply_output(on) {
+  return new Promise(function (resolve, reject) {
+    invoke('set_power_supply_output', { on: on })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_power_supply_status() {
+  return new Promise(function (resolve, reject) {
+    invoke('get_power_supply_status', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_voltage_protection_test(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_voltage_protection_test', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_voltage_protection_test(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_voltage_protection_test', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_voltage_protection_result(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_voltage_protection_result', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_start_boot_time_test(param, deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('start_boot_time_test', { param: param, deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_stop_boot_time_test(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('stop_boot_time_test', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_boot_time_result(deviceId = null) {
+  return new Promise(function (resolve, reject) {
+    invoke('get_boot_time_result', { deviceId: deviceId })
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_get_upgrade_progress() {
+  return new Promise(function (resolve, reject) {
+    invoke('get_upgrade_progress', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
+function cmd_cancel_motor_fw_upgrade() {
+  return new Promise(function (resolve, reject) {
+    invoke('cancel_motor_fw_upgrade', {})
+      .then((data) => {
+        resolve(data);
+      })
+      .catch((error) => {
+        console.log(error)
+        notify_failed(error)
+        reject(error)
+      })
+  })
+}
+
 export default {
   notify_success,
   notify_failed,
@@ -606,4 +1460,65 @@ export default {
   cmd_connect_relay,
   cmd_disconnect_relay,
   cmd_set_relay_power,
+  cmd_run_speed_step_test,
+  cmd_start_spd_autotune,
+  cmd_stop_spd_autotune,
+  cmd_get_spd_autotune_result,
+  cmd_apply_spd_autotune,
+  cmd_calc_current_loop_gains,
+  cmd_apply_current_loop_gains,
+  cmd_load_speed_profile_file,
+  cmd_start_speed_profile,
+  cmd_pause_speed_profile,
+  cmd_resume_speed_profile,
+  cmd_abort_speed_profile,
+  cmd_get_speed_profile_status,
+  cmd_get_startup_test_records,
+  cmd_export_startup_test_report,
+  cmd_start_startup_sweep,
+  cmd_stop_startup_sweep,
+  cmd_get_startup_sweep_result,
+  cmd_apply_startup_sweep_best,
+  cmd_pause_startup_task,
+  cmd_resume_startup_task,
+  cmd_load_startup_test_checkpoint,
+  cmd_resume_startup_task_from_checkpoint,
+  cmd_start_endurance_test,
+  cmd_stop_endurance_test,
+  cmd_get_endurance_test_result,
+  cmd_export_endurance_test_report,
+  cmd_load_test_plan_file,
+  cmd_start_test_plan,
+  cmd_stop_test_plan,
+  cmd_get_test_plan_result,
+  cmd_export_test_plan_report,
+  cmd_start_station_test,
+  cmd_stop_station_test,
+  cmd_get_station_status,
+  cmd_get_station_history,
+  cmd_list_connected_devices,
+  cmd_start_startup_stations,
+  cmd_stop_startup_stations,
+  cmd_stop_startup_station,
+  cmd_pause_startup_station,
+  cmd_resume_startup_station,
+  cmd_get_startup_stations_summary,
+  cmd_get_startup_station_records,
+  cmd_export_startup_station_report,
+  cmd_set_relay_channel,
+  cmd_get_relay_channels,
+  cmd_confirm_power_switch,
+  cmd_connect_power_supply,
+  cmd_disconnect_power_supply,
+  cmd_set_power_supply,
+  cmd_set_power_supply_output,
+  cmd_get_power_supply_status,
+  cmd_start_voltage_protection_test,
+  cmd_stop_voltage_protection_test,
+  cmd_get_voltage_protection_result,
+  cmd_start_boot_time_test,
+  cmd_stop_boot_time_test,
+  cmd_get_boot_time_result,
+  cmd_get_upgrade_progress,
+  cmd_cancel_motor_fw_upgrade,
 }
\ No newline at end of file